@@ -4,7 +4,7 @@ use genetic_algorithm_lib::Member;
 use image::imageops::{resize, FilterType};
 use image::{ImageBuffer, RgbImage};
 use image_lib::colour::Colour;
-use image_lib::images::grid::{AxisResolution, GridImage, Resolution};
+use image_lib::images::grid::{AxisResolution, GridImage, Resolution, Triangulated};
 
 #[derive(Clone)]
 pub struct ImageMember<'a, const W: usize, const H: usize> {
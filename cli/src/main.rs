@@ -6,8 +6,12 @@ use std::fs::File;
 use std::io::Write;
 use std::time::Instant;
 
-use genetic_algorithm_lib::{Member, Population};
-use image::{io::Reader, open, DynamicImage, Rgb, RgbImage};
+use genetic_algorithm_lib::workers::{serve, Decode, Encode, WorkerAddress, WorkerPool};
+use genetic_algorithm_lib::{
+    Coevolution, ConstraintHandling, EvaluatedPopulation, HallOfFame, LineageStore, LocalSearch,
+    Member, Population,
+};
+use image::{io::Reader, open, DynamicImage, Rgb, RgbImage, RgbaImage};
 use imageproc::{drawing::draw_polygon, point::Point};
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
@@ -15,11 +19,47 @@ use structopt::StructOpt;
 use cairo;
 use cairo::{Context, Format, ImageSurface, SvgSurface};
 use image::imageops::{resize, FilterType};
-use image_lib::colour::Colour;
+use image_lib::colour::{Colour, ColourDistance, ColourSpace};
 use image_lib::images::grid::{
-    AxisResolution, BreedMetadata, FitnessMetadata, GridImage, Resolution,
+    AxisResolution, BreedMetadata, ColourInitialisation, ColourMode, ColourSpecies, DynGridImage,
+    DynGridMember, FitnessMetadata, GeometrySpecies, MeshDefect, Resolution, StructureMutation,
+    Triangulated, VertexPlacement,
 };
 use rand::Rng;
+use std::str::FromStr;
+
+/// The number of nodes of the image's grid in each axis, given as `<width>x<height>`
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct GridSize(usize, usize);
+
+impl FromStr for GridSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("expected a grid size like `16x16`, found `{}`", s))?;
+        let size = (
+            width
+                .parse()
+                .map_err(|_| format!("invalid grid width `{}`", width))?,
+            height
+                .parse()
+                .map_err(|_| format!("invalid grid height `{}`", height))?,
+        );
+        if size.0 < 3 || size.1 < 3 {
+            return Err(format!("the grid must be at least 3x3, found `{}`", s));
+        }
+        // Every node needs its own position in the domain
+        if size.0 > 65536 || size.1 > 65536 {
+            return Err(format!(
+                "the grid must be at most 65536x65536, found `{}`",
+                s
+            ));
+        }
+        Ok(GridSize(size.0, size.1))
+    }
+}
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -39,34 +79,443 @@ struct TargetImage {
     /// generation count has been reached
     #[structopt(short, long)]
     time: Option<usize>,
+    /// The number of worker processes to spawn for fitness evaluation, by default none are
+    /// spawned
+    #[structopt(long)]
+    workers: Option<usize>,
+    /// The address of a fitness worker, either `host:port` for a TCP worker or `unix:<path>` for
+    /// a Unix socket worker, can be provided multiple times
+    #[structopt(long)]
+    peer: Vec<String>,
+    /// Runs as a fitness worker, answering requests on stdin and replying on stdout
+    #[structopt(long)]
+    worker: bool,
+    /// Runs as a fitness worker, answering requests from connections to this TCP address
+    #[structopt(long)]
+    listen: Option<String>,
+    /// The number of distinct best members to keep across the run, these are exported when
+    /// evolution finishes, by default 10
+    #[structopt(long)]
+    hall_of_fame: Option<usize>,
+    /// The number of extra fitness evaluations each member may use for local search
+    /// (greedy vertex moves by the structure mutation and colour tweaks), by default local search
+    /// isn't performed. Local search is performed in this process, so it can't be combined with
+    /// workers or constraint handling
+    #[structopt(long, conflicts_with_all = &["constraints", "workers", "peer"])]
+    local_search: Option<usize>,
+    /// Only the fitness found by local search is kept, the refined member itself is discarded
+    #[structopt(long)]
+    baldwinian: bool,
+    /// Records the lineage of every member and exports it to this path when evolution finishes,
+    /// as Graphviz DOT if the path ends in `.dot`, otherwise as JSON. Every member is kept in
+    /// memory until then, so long runs use memory in proportion to their length
+    #[structopt(long, parse(from_os_str))]
+    lineage: Option<PathBuf>,
+    /// How to handle members with inverted or degenerate triangles, one of `penalty:<weight>`,
+    /// `death`, `repair` or `feasibility`, by default they are not treated specially.
+    /// Constrained evaluation is performed in this process, so it can't be combined with workers
+    #[structopt(long, conflicts_with_all = &["workers", "peer"])]
+    constraints: Option<ConstraintHandling>,
+    /// Evolves the geometry and the colours of the image in separate, cooperating populations.
+    /// Each population is evaluated in this process, and only the best combined image is kept
+    #[structopt(
+        long,
+        conflicts_with_all = &[
+            "workers",
+            "peer",
+            "local-search",
+            "constraints",
+            "hall-of-fame",
+            "lineage",
+            "coarse-to-fine",
+        ]
+    )]
+    coevolve: bool,
+    /// Colours the vertices of the image and shades each triangle between them, rather than
+    /// giving each triangle a flat colour
+    #[structopt(long, conflicts_with = "palette")]
+    gouraud: bool,
+    /// Colours every triangle with one of a palette of this many colours (at most 256), e.g. the
+    /// inks available for printing. The palette is found by k-means clustering of the target and
+    /// is then evolved, the palette of each exported image is exported as a GIMP palette
+    #[structopt(long)]
+    palette: Option<usize>,
+    /// Initialises the colours of the image from the target rather than at random, one of
+    /// `mean` (the mean of the target under each triangle), `jitter:<amount>` (the mean moved by
+    /// up to the amount in each channel, so that the population is diverse) or `blur:<radius>`
+    /// (the mean of the target blurred by the radius in pixels)
+    #[structopt(long)]
+    initial_colours: Option<ColourInitialisation>,
+    /// Moves the vertices of the image towards the detail of the target before evolution, one of
+    /// `edges` (strong edges) or `saliency` (colours far from the target's mean colour)
+    #[structopt(long)]
+    vertex_placement: Option<VertexPlacement>,
+    /// How the difference between the image and the target is measured, one of `rgb`, `cie76`
+    /// or `ciede2000`
+    #[structopt(long, default_value = "rgb")]
+    distance: ColourDistance,
+    /// The colour space that colours are mutated in, one of `rgb`, `lab`, `oklab` or `hsv`
+    #[structopt(long, default_value = "rgb")]
+    mutation_space: ColourSpace,
+    /// How far vertices are moved by structure mutations, one of `uniform` (anywhere within
+    /// the vertex's surrounding polygon), `radius:<radius>` (within the radius, in the image's
+    /// 65536 x 65536 coordinates) or `gaussian:<sigma>`
+    #[structopt(long, default_value = "uniform")]
+    structure_mutation: StructureMutation,
+    /// The number of generations to evolve each coarser grid for before evolving the full grid,
+    /// each coarser grid has about half as many nodes in each axis as the next, down to at least
+    /// 4 x 4, e.g. a 17x17 grid is evolved as 5x5, then 9x9, then 17x17.
+    /// By default the full grid is evolved from the start
+    #[structopt(long)]
+    coarse_to_fine: Option<usize>,
+    /// The number of nodes of the image's grid in each axis, as `<width>x<height>`
+    #[structopt(long, default_value = "16x16")]
+    grid: GridSize,
+}
+
+fn load_target(path: &Path) -> RgbaImage {
+    Reader::open(path).unwrap().decode().unwrap().to_rgba8()
+}
+
+/// Creates a closure to get the colour of a pixel in the target, pixels beyond the edge of the
+/// target take the colour of the nearest edge pixel
+fn target_sampler(target: &RgbaImage) -> impl Fn(u16, u16) -> Colour + '_ {
+    let (target_width, target_height) = (target.width(), target.height());
+    move |x: u16, y: u16| {
+        let colour = target.get_pixel(
+            (x as u32).min(target_width - 1),
+            (y as u32).min(target_height - 1),
+        );
+        Colour::new_rgba(colour[0], colour[1], colour[2], colour[3])
+    }
+}
+
+/// Finds a palette of `count` colours for the target by k-means clustering, the target is
+/// sampled at up to 65536 pixels to keep clustering quick
+fn target_palette(target: &RgbaImage, count: usize) -> Vec<Colour> {
+    assert!(
+        0 < count && count <= 256,
+        "The palette must have between 1 and 256 colours"
+    );
+    let step = (target.width() as usize * target.height() as usize / 65536).max(1);
+    let pixels: Vec<Colour> = target
+        .pixels()
+        .step_by(step)
+        .map(|colour| Colour::new_rgba(colour[0], colour[1], colour[2], colour[3]))
+        .collect();
+    Colour::kmeans(&pixels, count)
+}
+
+/// Creates an image with the dimensions of the target and a grid of `grid` nodes, with its
+/// vertices placed and its colours initialised from the target as `args` requests. It is shaded
+/// if `--gouraud` is set, or coloured from `palette` if one is given
+fn new_dyn_target_image(
+    target: &RgbaImage,
+    grid: GridSize,
+    args: &TargetImage,
+    palette: Option<&[Colour]>,
+) -> DynGridImage {
+    let mut image =
+        DynGridImage::new_uniform_with_dimensions(grid.0, grid.1, target.width(), target.height());
+    if let Some(placement) = args.vertex_placement {
+        image.place_vertices(&target_sampler(target), placement);
+    }
+    if let Some(initialisation) = args.initial_colours {
+        image.initialise_colours(&target_sampler(target), initialisation);
+    }
+    if args.gouraud {
+        image.set_colour_mode(ColourMode::Gouraud);
+    }
+    if let Some(palette) = palette {
+        image.set_palette(palette.to_vec());
+        image.set_colour_mode(ColourMode::Palette);
+    }
+    image
+}
+
+/// Creates fitness metadata at a random offset within a raster pixel, so that every
+/// generation samples the target at slightly different points
+fn random_fitness_metadata(distance: ColourDistance) -> FitnessMetadata {
+    let resolution = Resolution(AxisResolution::Blocks64, AxisResolution::Blocks64);
+    FitnessMetadata::new_with_distance(
+        (
+            rand::thread_rng().gen_range(0..(resolution.0.get_pixel_size())) as u16,
+            rand::thread_rng().gen_range(0..(resolution.1.get_pixel_size())) as u16,
+        ),
+        resolution,
+        distance,
+    )
+}
+
+/// Exports a render of the image, at its real dimensions, as a PNG with a transparent background
+fn export_image<T: Triangulated>(image: &T, path: &Path) {
+    let (width, height) = image.get_dimensions();
+    let export = ImageSurface::create(Format::ARgb32, width as i32, height as i32).unwrap();
+    let context = Context::new(&export).unwrap();
+    image.rasterize_composited((width, height), (0, 0), Colour::transparent(), |p, c| {
+        context.set_source_rgba(
+            c.r as f64 / 255.0,
+            c.g as f64 / 255.0,
+            c.b as f64 / 255.0,
+            c.a as f64 / 255.0,
+        );
+        context.rectangle(p.x as f64, p.y as f64, 1.0, 1.0);
+        context.fill();
+    });
+    let mut f = File::create(path).unwrap();
+    export.write_to_png(&mut f);
+}
+
+/// Exports the image's triangles, at its real dimensions, as an SVG, shaded triangles are
+/// exported with the average colour of their vertices
+fn export_svg<T: Triangulated>(image: &T, path: &Path) {
+    let (width, height) = image.get_dimensions();
+    let export = SvgSurface::new(width as f64, height as f64, Some(path)).unwrap();
+    let context = Context::new(&export).unwrap();
+    // Scales a point from the image's domain to its dimensions
+    let scale = |p: image_lib::point::Point| {
+        (
+            p.x as f64 * width as f64 / 65536.0,
+            p.y as f64 * height as f64 / 65536.0,
+        )
+    };
+    image.get_triangles(|first, second, third, c| {
+        context.set_source_rgba(
+            c.r as f64 / 255.0,
+            c.g as f64 / 255.0,
+            c.b as f64 / 255.0,
+            c.a as f64 / 255.0,
+        );
+        let (x, y) = scale(first);
+        context.move_to(x, y);
+        for point in [second, third] {
+            let (x, y) = scale(point);
+            context.line_to(x, y);
+        }
+        context.close_path();
+        context.fill();
+    });
+    export.finish();
+}
+
+/// Exports a palette as a GIMP palette, with a line for each colour
+fn export_palette(palette: &[Colour], path: &Path) {
+    let mut f = File::create(path).unwrap();
+    writeln!(f, "GIMP Palette").unwrap();
+    writeln!(f, "Columns: {}", palette.len().min(16)).unwrap();
+    writeln!(f, "#").unwrap();
+    for (i, c) in palette.iter().enumerate() {
+        writeln!(f, "{:3} {:3} {:3}\tInk {}", c.r, c.g, c.b, i + 1).unwrap();
+    }
+}
+
+/// The state shared by every stage of genetic evolution, so that the coarser grids are
+/// evaluated, bred and recorded in the same way as the full grid
+struct Evolution<'a, U>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    args: &'a TargetImage,
+    /// The fitness workers, if any
+    pool: Option<WorkerPool>,
+    breed_metadata: BreedMetadata,
+    hall_of_fame: HallOfFame<DynGridMember<'a, U>>,
+    lineage: Option<LineageStore>,
+    /// The number of generations evaluated so far, over every grid
+    generation: usize,
+}
+
+impl<'a, U> Evolution<'a, U>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    /// Evaluates a generation by local search, constraint handling, the workers or in this
+    /// process, as requested, and records it in the hall of fame and the lineage
+    fn run(
+        &mut self,
+        population: Box<Population<DynGridMember<'a, U>, 25>>,
+    ) -> EvaluatedPopulation<DynGridMember<'a, U>, 25> {
+        let fitness_metadata = random_fitness_metadata(self.args.distance);
+        let executed_population = if let Some(budget) = self.args.local_search {
+            let mode = if self.args.baldwinian {
+                LocalSearch::Baldwinian
+            } else {
+                LocalSearch::Lamarckian
+            };
+            population.run_memetic(&fitness_metadata, &self.breed_metadata, mode, budget)
+        } else if let Some(handling) = self.args.constraints {
+            population.run_constrained(&fitness_metadata, handling)
+        } else if let Some(pool) = &mut self.pool {
+            population.run_distributed(&fitness_metadata, pool)
+        } else {
+            population.run(&fitness_metadata)
+        };
+        self.hall_of_fame
+            .record(&executed_population, self.generation);
+        if let Some(lineage) = &mut self.lineage {
+            lineage.record(&executed_population, self.generation);
+        }
+        self.generation += 1;
+        executed_population
+    }
+}
+
+/// Evolves a population for `--coarse-to-fine` generations, then refines every member of the
+/// final population to a grid of `size` nodes, keeping its appearance
+fn evolve_coarse<'a, U>(
+    evolution: &mut Evolution<'a, U>,
+    members: [DynGridMember<'a, U>; 25],
+    size: GridSize,
+) -> [DynGridMember<'a, U>; 25]
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    let mut population = Box::new(Population::new(members));
+    for _ in 1..evolution.args.coarse_to_fine.unwrap_or(0) {
+        let executed_population = evolution.run(population);
+        population = Box::new(executed_population.breed(&evolution.breed_metadata));
+    }
+    let executed_population = evolution.run(population);
+    let (width, height) = executed_population.best().0.get_image().get_grid_size();
+    println!(
+        "Refining the {}x{} grid to {}x{} with best fitness {}",
+        width,
+        height,
+        size.0,
+        size.1,
+        executed_population.best().1
+    );
+    executed_population
+        .members()
+        .each_ref()
+        .map(|(member, _)| member.resample(size.0, size.1))
+}
+
+/// Gets the coarser grids to evolve before a grid of `size` nodes, coarsest first, each has
+/// about half as many nodes in each axis as the next, so that a grid of `2^n + 1` nodes is
+/// refined exactly
+fn coarse_grid_sizes(size: GridSize) -> Vec<GridSize> {
+    let mut sizes = Vec::new();
+    let mut coarser = GridSize(size.0.div_ceil(2), size.1.div_ceil(2));
+    while coarser.0 >= 4 && coarser.1 >= 4 {
+        sizes.insert(0, coarser);
+        coarser = GridSize(coarser.0.div_ceil(2), coarser.1.div_ceil(2));
+    }
+    sizes
+}
+
+/// Answers fitness requests for grid image members, of any grid size, against the target
+fn serve_fitness<R, W, U>(reader: R, writer: W, get_target_pixel: &U) -> std::io::Result<()>
+where
+    R: std::io::Read,
+    W: std::io::Write,
+    U: Fn(u16, u16) -> Colour,
+{
+    serve(reader, writer, |genome, metadata| {
+        let image = DynGridImage::decode(genome)?;
+        let metadata = FitnessMetadata::decode(metadata)?;
+        Some(DynGridMember::new(image, get_target_pixel).fitness(&metadata))
+    })
+}
+
+/// Runs a fitness worker, either on stdin/stdout or on a TCP listener
+fn do_fitness_worker(args: TargetImage) {
+    let target = load_target(&args.target);
+    let get_target_pixel = target_sampler(&target);
+
+    match args.listen {
+        Some(address) => {
+            let listener = std::net::TcpListener::bind(address).unwrap();
+            // Each connection is served on its own thread
+            std::thread::scope(|scope| {
+                for stream in listener.incoming().flatten() {
+                    let get_target_pixel = &get_target_pixel;
+                    scope.spawn(move || {
+                        let reader = stream.try_clone().unwrap();
+                        let _ = serve_fitness(reader, stream, get_target_pixel);
+                    });
+                }
+            });
+        }
+        None => {
+            serve_fitness(
+                std::io::stdin().lock(),
+                std::io::stdout().lock(),
+                &get_target_pixel,
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Gets the address of a worker given by `--peer`, either `unix:<path>` or a TCP address
+fn peer_address(peer: &str) -> WorkerAddress {
+    #[cfg(unix)]
+    if let Some(path) = peer.strip_prefix("unix:") {
+        return WorkerAddress::Unix(PathBuf::from(path));
+    }
+    #[cfg(not(unix))]
+    assert!(
+        !peer.starts_with("unix:"),
+        "Unix socket workers are only supported on Unix"
+    );
+    WorkerAddress::Tcp(peer.to_string())
 }
 
 fn do_genetic_evolution(args: TargetImage) {
     // Loads the image
-    let target = Reader::open(args.target)
-        .unwrap()
-        .decode()
-        .unwrap()
-        .to_rgb8();
-    let (target_width, target_height) = (target.width(), target.height());
+    let target = load_target(&args.target);
     println!("Loaded target file");
+    let get_target_pixel = target_sampler(&target);
+    let palette = args.palette.map(|count| target_palette(&target, count));
 
-    // A closure to get the colour of a pixel at a point in the target, it assumes that x and y is
-    // already normalised to between 0..65536
-    let get_target_pixel = |x: u16, y: u16| {
-        let colour = target.get_pixel(
-            x as u32 * target_width / 65536_u32,
-            y as u32 * target_height / 65536_u32,
-        );
-        Colour::new(colour[0], colour[1], colour[2])
+    // Connects to any fitness workers, spawned workers are copies of this executable
+    let mut addresses: Vec<WorkerAddress> = (0..args.workers.unwrap_or(0))
+        .map(|_| WorkerAddress::Process {
+            program: std::env::current_exe().unwrap(),
+            args: vec![
+                args.target.to_string_lossy().into_owned(),
+                "--worker".to_string(),
+            ],
+        })
+        .collect();
+    addresses.extend(args.peer.iter().map(|peer| peer_address(peer)));
+    let pool = if addresses.is_empty() {
+        None
+    } else {
+        Some(WorkerPool::new(addresses))
+    };
+    let mut evolution = Evolution {
+        args: &args,
+        pool,
+        breed_metadata: BreedMetadata::new_with_mutations(
+            0.05,
+            args.mutation_space,
+            args.structure_mutation,
+        ),
+        hall_of_fame: HallOfFame::new(args.hall_of_fame.unwrap_or(10)),
+        lineage: args.lineage.as_ref().map(|_| LineageStore::new()),
+        generation: 0,
+    };
+
+    // Creates the initial population, evolving it on coarser grids first if requested
+    let mut sizes = if args.coarse_to_fine.unwrap_or(0) > 0 {
+        coarse_grid_sizes(args.grid)
+    } else {
+        Vec::new()
     };
-    // Creates the initial population
-    let mut population = Box::new(Population::new([0; 25].map(|_| {
-        image_lib::images::grid::GAImageMember::new(
-            GridImage::<16, 16>::new_uniform(),
+    sizes.push(args.grid);
+    let mut members = [0; 25].map(|_| {
+        DynGridMember::new(
+            new_dyn_target_image(&target, sizes[0], &args, palette.as_deref()),
             &get_target_pixel,
         )
-    })));
+    });
+    // Evolves each coarser grid and refines it to the next grid
+    for size in &sizes[1..] {
+        members = evolve_coarse(&mut evolution, members, *size);
+    }
+    let mut population = Box::new(Population::new(members));
 
     // Goes into main loop
     let start_time = Utc::now();
@@ -79,15 +528,7 @@ fn do_genetic_evolution(args: TargetImage) {
         None => true,
     } {
         // Runs the population
-        let resolution = Resolution(AxisResolution::Blocks64, AxisResolution::Blocks64);
-        let fitness_metadata = FitnessMetadata::new(
-            (
-                rand::thread_rng().gen_range(0..(resolution.0.get_pixel_size())) as u16,
-                rand::thread_rng().gen_range(0..(resolution.1.get_pixel_size())) as u16,
-            ),
-            resolution,
-        );
-        let executed_population = population.run(&fitness_metadata);
+        let executed_population = evolution.run(population);
         let best = executed_population.best().0;
 
         if generation % 250 == 0 {
@@ -101,48 +542,52 @@ fn do_genetic_evolution(args: TargetImage) {
                 (now.time() - start_time.time()).num_minutes(),
                 (now.time() - start_time.time()).num_seconds()
             );
+            report_defects(&best.get_image().validate());
 
-            // Exports the best members, image
-            let mut export = ImageSurface::create(Format::Rgb24, 3 * 1024, 1024).unwrap();
+            // Exports the best members, image, each panel is the size of the target
+            let (width, height) = best.get_image().get_dimensions();
+            let mut export =
+                ImageSurface::create(Format::ARgb32, 3 * width as i32, height as i32).unwrap();
             let context = Context::new(&export).unwrap();
 
             // Renders the target image
-            let resolution = Resolution(AxisResolution::Blocks1024, AxisResolution::Blocks1024);
-            let Resolution(x_resolution, y_resolution) = resolution;
-
-            best.get_image()
-                .rasterize_scanline(resolution, (0, 0), |p, c| {
-                    let tp = get_target_pixel(
-                        (p.x as u32 * x_resolution.get_pixel_size()) as u16,
-                        (p.y as u32 * y_resolution.get_pixel_size()) as u16,
-                    );
-                    // Renders the difference
+            best.get_image().rasterize_composited(
+                (width, height),
+                (0, 0),
+                Colour::transparent(),
+                |p, c| {
+                    let tp = get_target_pixel(p.x, p.y);
+                    // Renders the difference, of the colours premultiplied by their alpha
+                    let ((cr, cg, cb), (tr, tg, tb)) = (c.premultiplied(), tp.premultiplied());
                     context.set_source_rgb(
-                        (c.r as i32 - tp.r as i32).abs() as f64 / 256.0,
-                        (c.g as i32 - tp.g as i32).abs() as f64 / 256.0,
-                        (c.b as i32 - tp.b as i32).abs() as f64 / 256.0,
+                        (cr as i32 - tr as i32).abs() as f64 / 256.0,
+                        (cg as i32 - tg as i32).abs() as f64 / 256.0,
+                        (cb as i32 - tb as i32).abs() as f64 / 256.0,
                     );
                     context.rectangle(p.x as f64, p.y as f64, 1.0, 1.0);
                     context.fill();
 
                     // Renders the target image pixel
-                    context.set_source_rgb(
-                        tp.r as f64 / 256 as f64,
-                        tp.g as f64 / 256 as f64,
-                        tp.b as f64 / 256 as f64,
+                    context.set_source_rgba(
+                        tp.r as f64 / 255.0,
+                        tp.g as f64 / 255.0,
+                        tp.b as f64 / 255.0,
+                        tp.a as f64 / 255.0,
                     );
-                    context.rectangle(p.x as f64 * 1.0 + 1024.0, p.y as f64 * 1.0, 1.0, 1.0);
+                    context.rectangle(p.x as f64 + width as f64, p.y as f64, 1.0, 1.0);
                     context.fill();
 
                     // Renders the image pixel
-                    context.set_source_rgb(
-                        c.r as f64 / 256 as f64,
-                        c.g as f64 / 256 as f64,
-                        c.b as f64 / 256 as f64,
+                    context.set_source_rgba(
+                        c.r as f64 / 255.0,
+                        c.g as f64 / 255.0,
+                        c.b as f64 / 255.0,
+                        c.a as f64 / 255.0,
                     );
-                    context.rectangle(p.x as f64 * 1.0 + 2048.0, p.y as f64 * 1.0, 1.0, 1.0);
+                    context.rectangle(p.x as f64 + 2.0 * width as f64, p.y as f64, 1.0, 1.0);
                     context.fill();
-                });
+                },
+            );
 
             // export.finish();
             let mut f = std::fs::File::create(Path::new(
@@ -158,8 +603,42 @@ fn do_genetic_evolution(args: TargetImage) {
         }
         generation += 1;
         // Applies natural selection to get the next generation
-        let breed_metadata = BreedMetadata::new(0.05);
-        population = Box::new(executed_population.breed(&breed_metadata));
+        population = Box::new(executed_population.breed(&evolution.breed_metadata));
+    }
+
+    // Exports the hall of fame, as renders, as vectors and as encoded genomes
+    std::fs::create_dir_all("./foo/hall_of_fame").unwrap();
+    for (rank, entry) in evolution.hall_of_fame.entries().iter().enumerate() {
+        let name = format!(
+            "./foo/hall_of_fame/rank_{}_gen_{}_fitness_{}",
+            rank, entry.generation, entry.fitness
+        );
+        export_image(
+            entry.member.get_image(),
+            Path::new(&format!("{}.png", name)),
+        );
+        export_svg(
+            entry.member.get_image(),
+            Path::new(&format!("{}.svg", name)),
+        );
+        let mut genome = Vec::new();
+        entry.member.get_image().encode(&mut genome);
+        std::fs::write(format!("{}.genome", name), genome).unwrap();
+        if entry.member.get_image().get_colour_mode() == ColourMode::Palette {
+            export_palette(
+                entry.member.get_image().get_palette(),
+                Path::new(&format!("{}.gpl", name)),
+            );
+        }
+    }
+
+    // Exports the lineage of every member
+    if let (Some(path), Some(lineage)) = (&args.lineage, &evolution.lineage) {
+        let export = match path.extension() {
+            Some(extension) if extension == "dot" => lineage.to_dot(),
+            _ => lineage.to_json(),
+        };
+        std::fs::write(path, export).unwrap();
     }
 
     // Prints final results
@@ -171,9 +650,96 @@ fn do_genetic_evolution(args: TargetImage) {
     );
 }
 
+/// Evolves the image's geometry and colours as two cooperating species, the best combined image
+/// is exported every 250 generations
+fn do_cooperative_coevolution(args: TargetImage) {
+    let target = load_target(&args.target);
+    println!("Loaded target file");
+    let get_target_pixel = target_sampler(&target);
+    let palette = args.palette.map(|count| target_palette(&target, count));
+
+    let mut coevolution: Box<Coevolution<_, _, _, 25, 25>> = Box::new(Coevolution::new(
+        [0; 25].map(|_| {
+            GeometrySpecies::new(
+                new_dyn_target_image(&target, args.grid, &args, palette.as_deref()),
+                &get_target_pixel,
+            )
+        }),
+        [0; 25].map(|_| {
+            ColourSpecies::new(
+                new_dyn_target_image(&target, args.grid, &args, palette.as_deref()),
+                &get_target_pixel,
+            )
+        }),
+    ));
+    let breed_metadata =
+        BreedMetadata::new_with_mutations(0.05, args.mutation_space, args.structure_mutation);
+
+    let start_time = Utc::now();
+    let mut generation = 0;
+    while match args.generations {
+        Some(n) => generation < n,
+        None => true,
+    } && match args.time {
+        Some(n) => (Utc::now().time() - start_time.time()).num_seconds() < n as i64,
+        None => true,
+    } {
+        let (next, fitness) = coevolution.run(
+            &random_fitness_metadata(args.distance),
+            &breed_metadata,
+            &breed_metadata,
+        );
+        *coevolution = next;
+
+        if generation % 250 == 0 {
+            println!(
+                "Best combined fitness for generation {} is {}",
+                generation, fitness
+            );
+            let (geometry, colours) = coevolution.best();
+            report_defects(&geometry.combine(colours).validate());
+            std::fs::create_dir_all("./foo").unwrap();
+            export_image(
+                &geometry.combine(colours),
+                Path::new(&format!(
+                    "./foo/coevolution_gen_{}_fitness_{}.png",
+                    generation, fitness
+                )),
+            );
+        }
+        generation += 1;
+    }
+
+    println!(
+        "Cooperative coevolution started at {} and finished at {}, performing {} generations",
+        start_time.to_rfc3339(),
+        Utc::now().to_rfc3339(),
+        generation
+    );
+}
+
+/// Reports the defects of the best member's mesh, which should never have any
+fn report_defects(defects: &[MeshDefect]) {
+    if !defects.is_empty() {
+        println!(
+            "The best member's mesh has {} defects, the first being {:?}",
+            defects.len(),
+            defects[0]
+        );
+    }
+}
+
 fn main() {
     // Target Image Mode -> Genetic Evolution towards a target image
     let args = TargetImage::from_args();
-    println!("{:?}", args);
-    do_genetic_evolution(args);
+    // Workers reply on stdout, so nothing else may be printed in worker mode
+    if args.worker || args.listen.is_some() {
+        do_fitness_worker(args);
+    } else if args.coevolve {
+        println!("{:?}", args);
+        do_cooperative_coevolution(args);
+    } else {
+        println!("{:?}", args);
+        do_genetic_evolution(args);
+    }
 }
@@ -0,0 +1,80 @@
+use crate::{Member, Population};
+
+/// The fitness metadata of a species in cooperative coevolution, a member is evaluated by
+/// combining it with a collaborator from the other species
+pub struct Collaboration<T, F> {
+    /// The member of the other species to combine with
+    pub collaborator: T,
+    /// The metadata used to evaluate the combined member
+    pub metadata: F,
+}
+
+/// A cooperative coevolution driver, the two species A and B each hold part of a solution and
+/// are evolved in separate populations of CA and CB members respectively.
+/// Each species is scored by combining it with the best member of the other species
+pub struct Coevolution<A, B, F, const CA: usize, const CB: usize>
+where
+    A: Member<FitnessMetadata = Collaboration<B, F>> + Clone,
+    B: Member<FitnessMetadata = Collaboration<A, F>> + Clone,
+{
+    first: Population<A, CA>,
+    second: Population<B, CB>,
+    /// The collaborators, the best member of each species from the last generation
+    best: (A, B),
+}
+
+impl<A, B, F, const CA: usize, const CB: usize> Coevolution<A, B, F, CA, CB>
+where
+    A: Member<FitnessMetadata = Collaboration<B, F>> + Clone,
+    B: Member<FitnessMetadata = Collaboration<A, F>> + Clone,
+    F: Clone,
+{
+    /// Creates the driver, the first member of each population is used as the initial
+    /// collaborator for the other species
+    pub fn new(first: [A; CA], second: [B; CB]) -> Self {
+        let best = (first[0].clone(), second[0].clone());
+        Coevolution {
+            first: Population::new(first),
+            second: Population::new(second),
+            best,
+        }
+    }
+
+    /// Evaluates and breeds a generation of both species.
+    /// The first species is evaluated with the best of the second, and then the second species
+    /// is evaluated with the new best of the first.
+    /// Returns the next generation along with the fitness of the best combined solution
+    pub fn run(
+        self,
+        metadata: &F,
+        first_metadata: &A::BreedMetadata,
+        second_metadata: &B::BreedMetadata,
+    ) -> (Self, u64) {
+        let (_, best_second) = self.best;
+        let first = self.first.run(&Collaboration {
+            collaborator: best_second,
+            metadata: metadata.clone(),
+        });
+        let best_first = first.best().0.clone();
+
+        let second = self.second.run(&Collaboration {
+            collaborator: best_first.clone(),
+            metadata: metadata.clone(),
+        });
+        let (best_second, fitness) = second.best();
+
+        (
+            Coevolution {
+                best: (best_first, best_second.clone()),
+                first: first.breed(first_metadata),
+                second: second.breed(second_metadata),
+            },
+            fitness,
+        )
+    }
+
+    /// Gets the best member of each species, together these form the best solution
+    pub fn best(&self) -> (&A, &B) {
+        (&self.best.0, &self.best.1)
+    }
+}
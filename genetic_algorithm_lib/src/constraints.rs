@@ -0,0 +1,92 @@
+use crate::Member;
+use std::str::FromStr;
+
+/// A member that can violate constraints, violations are reported separately from fitness so
+/// that the population can decide how to handle them
+pub trait Constrained: Member {
+    /// The total magnitude of the member's constraint violations, 0 if the member is feasible
+    fn violation(&self, metadata: &Self::FitnessMetadata) -> u64;
+
+    /// Attempts to make the member feasible, this is required so that
+    /// `ConstraintHandling::Repair` never silently behaves as the death penalty
+    fn repair(&mut self, metadata: &Self::FitnessMetadata);
+}
+
+/// How infeasible members are treated when a population is evaluated
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConstraintHandling {
+    /// The fitness is reduced by the given weight for each unit of violation
+    Penalty(u64),
+    /// Infeasible members are given a fitness of 0
+    DeathPenalty,
+    /// Members are repaired before they are evaluated, any that are still infeasible are given
+    /// a fitness of 0
+    Repair,
+    /// Deb's rules; feasible members are fitter than infeasible members, feasible members are
+    /// ranked by fitness and infeasible members are ranked by violation, smallest first
+    FeasibilityFirst,
+}
+
+impl ConstraintHandling {
+    /// Maps each member's (fitness, violation) to the fitness used for selection
+    pub fn apply<const C: usize>(self, evaluations: [(u64, u64); C]) -> [u64; C] {
+        match self {
+            ConstraintHandling::Penalty(weight) => evaluations.map(|(fitness, violation)| {
+                fitness.saturating_sub(weight.saturating_mul(violation))
+            }),
+            ConstraintHandling::DeathPenalty | ConstraintHandling::Repair => {
+                evaluations.map(|(fitness, violation)| if violation > 0 { 0 } else { fitness })
+            }
+            ConstraintHandling::FeasibilityFirst => {
+                // Infeasible members are placed in 0..=max_violation, and feasible members are
+                // placed above that, which preserves Deb's ordering
+                let max_violation = evaluations.iter().map(|(_, v)| *v).max().unwrap_or(0);
+                evaluations.map(|(fitness, violation)| {
+                    if violation > 0 {
+                        max_violation - violation
+                    } else {
+                        fitness.saturating_add(max_violation + 1)
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Parses `penalty:<weight>`, `death`, `repair` or `feasibility`
+impl FromStr for ConstraintHandling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "death" => Ok(ConstraintHandling::DeathPenalty),
+            "repair" => Ok(ConstraintHandling::Repair),
+            "feasibility" => Ok(ConstraintHandling::FeasibilityFirst),
+            _ => match s.strip_prefix("penalty:").map(u64::from_str) {
+                Some(Ok(weight)) => Ok(ConstraintHandling::Penalty(weight)),
+                _ => Err(format!("unknown constraint handling `{}`", s)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feasibility_first_follows_debs_rules() {
+        let fitness =
+            ConstraintHandling::FeasibilityFirst.apply([(5, 0), (100, 3), (90, 1), (0, 0)]);
+        // Feasible members beat infeasible ones regardless of their raw fitness
+        assert!(fitness[0] > fitness[3] && fitness[3] > fitness[2]);
+        // Smaller violations beat larger ones
+        assert!(fitness[2] > fitness[1]);
+    }
+
+    #[test]
+    fn large_penalties_saturate() {
+        let fitness = ConstraintHandling::Penalty(u64::MAX).apply([(100, 2), (100, 0)]);
+        assert_eq!(fitness, [0, 100]);
+    }
+}
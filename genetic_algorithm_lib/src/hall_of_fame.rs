@@ -0,0 +1,103 @@
+use crate::{EvaluatedPopulation, Member};
+
+/// A member that has been entered into the hall of fame
+#[derive(Clone)]
+pub struct HallOfFameEntry<M> {
+    pub member: M,
+    pub fitness: u64,
+    /// The generation that the member was (last) recorded in
+    pub generation: usize,
+}
+
+/// Keeps the best distinct members seen across every generation of a run, since a population's
+/// members are consumed when it is bred
+pub struct HallOfFame<M: Member + Clone + PartialEq> {
+    /// The maximum number of members kept
+    capacity: usize,
+    /// The entries, sorted by fitness, largest first
+    entries: Vec<HallOfFameEntry<M>>,
+}
+
+impl<M: Member + Clone + PartialEq> HallOfFame<M> {
+    pub fn new(capacity: usize) -> HallOfFame<M> {
+        HallOfFame {
+            capacity,
+            entries: Vec::with_capacity(capacity + 1),
+        }
+    }
+
+    /// Records the members of an evaluated population that are fit enough to enter the hall
+    pub fn record<const C: usize>(
+        &mut self,
+        population: &EvaluatedPopulation<M, C>,
+        generation: usize,
+    ) {
+        // Members are sorted smallest first, so the best are visited first
+        for (member, fitness) in population.members().iter().rev() {
+            // Once the hall is full, nothing less fit than the last entry can enter
+            if self.entries.len() == self.capacity
+                && self.entries.last().is_none_or(|e| e.fitness >= *fitness)
+            {
+                break;
+            }
+            self.insert(member, *fitness, generation);
+        }
+    }
+
+    fn insert(&mut self, member: &M, fitness: u64, generation: usize) {
+        // Members are only kept once, with the best fitness they have been seen with
+        if let Some(i) = self.entries.iter().position(|e| e.member == *member) {
+            if self.entries[i].fitness >= fitness {
+                return;
+            }
+            self.entries.remove(i);
+        }
+        let position = self.entries.partition_point(|e| e.fitness >= fitness);
+        self.entries.insert(
+            position,
+            HallOfFameEntry {
+                member: member.clone(),
+                fitness,
+                generation,
+            },
+        );
+        self.entries.truncate(self.capacity);
+    }
+
+    /// Gets the entries, best first
+    pub fn entries(&self) -> &[HallOfFameEntry<M>] {
+        &self.entries
+    }
+
+    /// Gets the best entry, if any member has been recorded
+    pub fn best(&self) -> Option<&HallOfFameEntry<M>> {
+        self.entries.first()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::OneMax;
+    use crate::Population;
+
+    #[test]
+    fn keeps_the_best_distinct_members() {
+        let bits = |n: usize| {
+            let mut i = 0;
+            OneMax::<4>::from_bits([0; 4].map(|_| {
+                i += 1;
+                i <= n
+            }))
+        };
+        let mut hall_of_fame = HallOfFame::new(2);
+
+        hall_of_fame.record(&Population::new([bits(1), bits(3), bits(3)]).run(&()), 0);
+        hall_of_fame.record(&Population::new([bits(2), bits(4)]).run(&()), 1);
+
+        let entries = hall_of_fame.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].fitness, entries[0].generation), (4, 1));
+        assert_eq!((entries[1].fitness, entries[1].generation), (3, 0));
+    }
+}
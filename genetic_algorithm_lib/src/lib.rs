@@ -2,4 +2,23 @@ mod member;
 pub use member::Member;
 
 mod population;
-pub use population::Population;
+pub use population::{EvaluatedPopulation, Population};
+
+mod coevolution;
+pub use coevolution::{Coevolution, Collaboration};
+
+mod constraints;
+pub use constraints::{Constrained, ConstraintHandling};
+
+mod local_search;
+pub use local_search::{LocalSearch, Refine};
+
+mod lineage;
+pub use lineage::{Lineage, LineageRecord, LineageStore, Traceable};
+
+mod hall_of_fame;
+pub use hall_of_fame::{HallOfFame, HallOfFameEntry};
+
+pub mod problems;
+
+pub mod workers;
@@ -0,0 +1,341 @@
+use crate::{EvaluatedPopulation, Member};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// The identity of a member, its parents and the operators that were applied to create it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Lineage {
+    key: u64,
+    parents: Vec<u64>,
+    operators: Vec<&'static str>,
+}
+
+impl Lineage {
+    /// Creates the lineage of a member without parents, e.g. a member of the initial population
+    pub fn new() -> Lineage {
+        Lineage {
+            key: rand::random(),
+            parents: Vec::new(),
+            operators: Vec::new(),
+        }
+    }
+
+    /// Creates the lineage of a member bred from `parents`
+    pub fn child(parents: &[&Lineage]) -> Lineage {
+        Lineage {
+            parents: parents.iter().map(|p| p.key).collect(),
+            ..Lineage::new()
+        }
+    }
+
+    /// Records that an operator was applied to the member
+    pub fn record(&mut self, operator: &'static str) {
+        self.operators.push(operator);
+    }
+
+    /// Gets the random key that tells the member apart from every other member, the
+    /// `LineageStore` gives the members it records readable ids
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Gets the keys of the member's parents
+    pub fn parents(&self) -> &[u64] {
+        &self.parents
+    }
+
+    pub fn operators(&self) -> &[&'static str] {
+        &self.operators
+    }
+}
+
+impl Default for Lineage {
+    fn default() -> Self {
+        Lineage::new()
+    }
+}
+
+/// A member that carries its lineage
+pub trait Traceable {
+    fn lineage(&self) -> &Lineage;
+}
+
+/// A member's lineage, along with how it performed. Ids are given by the store, in the order
+/// that it first sees each member
+#[derive(Clone, Debug)]
+pub struct LineageRecord {
+    pub id: u64,
+    pub parents: Vec<u64>,
+    pub operators: Vec<&'static str>,
+    /// The generation the member was evaluated in
+    pub generation: usize,
+    pub fitness: u64,
+}
+
+/// Records the lineage of every evaluated member, so that the operators and parents that
+/// produce improvements can be analysed. Every record is kept unless the store is limited to
+/// the latest generations, so an unlimited store grows with the length of the run
+#[derive(Default)]
+pub struct LineageStore {
+    records: Vec<LineageRecord>,
+    /// The id of every member that has been recorded or is the parent of a recorded member, by
+    /// the member's key
+    ids: HashMap<u64, u64>,
+    /// The id given to the next member that the store sees
+    next_id: u64,
+    /// The keys of the members that have been recorded, members are only recorded once
+    recorded: HashSet<u64>,
+    /// The number of the latest generations whose records are kept, if not every generation
+    generations: Option<usize>,
+}
+
+impl LineageStore {
+    /// Creates a store which keeps the record of every member
+    pub fn new() -> LineageStore {
+        LineageStore::default()
+    }
+
+    /// Creates a store which only keeps the records of the latest `generations` generations, the
+    /// parents of the oldest records kept are referenced by id but have no records
+    pub fn new_with_generations(generations: usize) -> LineageStore {
+        assert!(generations > 0, "At least one generation must be kept");
+        LineageStore {
+            generations: Some(generations),
+            ..LineageStore::default()
+        }
+    }
+
+    /// Records every member of an evaluated population
+    pub fn record<M, const C: usize>(
+        &mut self,
+        population: &EvaluatedPopulation<M, C>,
+        generation: usize,
+    ) where
+        M: Member + Clone + Traceable,
+    {
+        for (member, fitness) in population.members() {
+            let lineage = member.lineage();
+            if self.recorded.insert(lineage.key()) {
+                let record = LineageRecord {
+                    id: self.get_id(lineage.key()),
+                    parents: lineage.parents().iter().map(|p| self.get_id(*p)).collect(),
+                    operators: lineage.operators().to_vec(),
+                    generation,
+                    fitness: *fitness,
+                };
+                self.records.push(record);
+            }
+        }
+        if let Some(generations) = self.generations {
+            self.prune(generation, generations);
+        }
+    }
+
+    pub fn records(&self) -> &[LineageRecord] {
+        &self.records
+    }
+
+    /// Gets the id of the member with the given key, giving it the next id if it hasn't been seen
+    fn get_id(&mut self, key: u64) -> u64 {
+        let next_id = &mut self.next_id;
+        *self.ids.entry(key).or_insert_with(|| {
+            *next_id += 1;
+            *next_id - 1
+        })
+    }
+
+    /// Drops the records older than the latest `generations` generations. Ids are given in the
+    /// order that members are seen, so any id older than every id the kept records reference is
+    /// no longer needed
+    fn prune(&mut self, generation: usize, generations: usize) {
+        self.records
+            .retain(|record| record.generation + generations > generation);
+        let oldest = self
+            .records
+            .iter()
+            .flat_map(|record| record.parents.iter().chain([&record.id]))
+            .min()
+            .copied()
+            .unwrap_or(self.next_id);
+        self.ids.retain(|_, id| *id >= oldest);
+        let ids = &self.ids;
+        self.recorded.retain(|key| ids.contains_key(key));
+    }
+
+    /// Exports the lineage as a Graphviz DOT digraph, edges run from parent to child and are
+    /// labelled with the operators that were applied to the child
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph lineage {\n");
+        for record in &self.records {
+            writeln!(
+                dot,
+                "    n{} [label=\"{}\\ngen {}\\nfitness {}\"];",
+                record.id, record.id, record.generation, record.fitness
+            )
+            .unwrap();
+            let operators: Vec<String> = record.operators.iter().map(|o| escape(o)).collect();
+            for parent in &record.parents {
+                writeln!(
+                    dot,
+                    "    n{} -> n{} [label=\"{}\"];",
+                    parent,
+                    record.id,
+                    operators.join("\\n")
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the lineage as a JSON array of records
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, record) in self.records.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let parents: Vec<String> = record.parents.iter().map(|p| p.to_string()).collect();
+            let operators: Vec<String> = record
+                .operators
+                .iter()
+                .map(|o| format!("\"{}\"", escape(o)))
+                .collect();
+            write!(
+                json,
+                "\n  {{\"id\": {}, \"parents\": [{}], \"operators\": [{}], \"generation\": {}, \"fitness\": {}}}",
+                record.id,
+                parents.join(", "),
+                operators.join(", "),
+                record.generation,
+                record.fitness
+            )
+            .unwrap();
+        }
+        json.push_str("\n]\n");
+        json
+    }
+}
+
+/// Escapes the backslashes and quotes of a string, so that it can be quoted in DOT or JSON
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Population;
+
+    /// A member whose fitness is fixed, its children are one fitter than their left parent
+    #[derive(Clone)]
+    struct Traced {
+        lineage: Lineage,
+        fitness: u64,
+    }
+
+    impl Member for Traced {
+        type FitnessMetadata = ();
+        type BreedMetadata = &'static str;
+
+        fn fitness(&self, _metadata: &()) -> u64 {
+            self.fitness
+        }
+
+        fn breed(left: &Self, right: &Self, operator: &&'static str) -> Self {
+            let mut lineage = Lineage::child(&[&left.lineage, &right.lineage]);
+            lineage.record(operator);
+            Traced {
+                lineage,
+                fitness: left.fitness + 1,
+            }
+        }
+    }
+
+    impl Traceable for Traced {
+        fn lineage(&self) -> &Lineage {
+            &self.lineage
+        }
+    }
+
+    fn initial() -> Population<Traced, 2> {
+        Population::new([0, 0].map(|fitness| Traced {
+            lineage: Lineage::new(),
+            fitness,
+        }))
+    }
+
+    #[test]
+    fn records_are_numbered_by_the_store() {
+        let mut store = LineageStore::new();
+        let evaluated = initial().run(&());
+        store.record(&evaluated, 0);
+        store.record(&evaluated, 0);
+        store.record(&evaluated.breed(&"mutate \"x\"\\y").run(&()), 1);
+
+        let records = store.records();
+        assert_eq!(records.len(), 4);
+        let ids: Vec<u64> = records.iter().map(|r| r.id).collect();
+        assert_eq!(ids, [0, 1, 2, 3]);
+        for record in &records[2..] {
+            assert_eq!(record.generation, 1);
+            assert_eq!(record.fitness, 1);
+            assert!(record.parents.iter().all(|p| *p < 2));
+        }
+
+        // The ids start again for every store
+        let mut other = LineageStore::new();
+        other.record(&initial().run(&()), 0);
+        assert_eq!(other.records()[0].id, 0);
+    }
+
+    #[test]
+    fn exports_link_parents_and_escape_operators() {
+        let mut store = LineageStore::new();
+        let evaluated = initial().run(&());
+        store.record(&evaluated, 0);
+        let child = evaluated.breed(&"mutate \"x\"\\y").run(&());
+        store.record(&child, 1);
+        let record = &store.records()[2];
+        let parent = record.parents[0];
+
+        let dot = store.to_dot();
+        assert!(dot.starts_with("digraph lineage {\n"));
+        assert!(dot.contains(&format!(
+            "    n{} [label=\"{}\\ngen 1\\nfitness 1\"];\n",
+            record.id, record.id
+        )));
+        assert!(dot.contains(&format!(
+            "    n{} -> n{} [label=\"mutate \\\"x\\\"\\\\y\"];\n",
+            parent, record.id
+        )));
+
+        let json = store.to_json();
+        assert!(json.contains(&format!(
+            "{{\"id\": {}, \"parents\": [{}, {}], \"operators\": [\"mutate \\\"x\\\"\\\\y\"], \"generation\": 1, \"fitness\": 1}}",
+            record.id, record.parents[0], record.parents[1]
+        )));
+        assert!(json.starts_with("[\n  {\"id\": 0, \"parents\": [], \"operators\": []"));
+    }
+
+    #[test]
+    fn limited_stores_keep_the_latest_generations() {
+        let mut store = LineageStore::new_with_generations(2);
+        let mut population = initial();
+        for generation in 0..10 {
+            let evaluated = population.run(&());
+            store.record(&evaluated, generation);
+            population = evaluated.breed(&"crossover");
+        }
+        assert_eq!(store.records().len(), 4);
+        assert!(store.records().iter().all(|r| r.generation >= 8));
+        // Only the members of the kept generations and their parents have ids
+        assert!(store.ids.len() <= 6);
+        // The kept records still link to their parents
+        let ids: Vec<u64> = store.records().iter().map(|r| r.id).collect();
+        for record in &store.records()[2..] {
+            assert!(record.parents.iter().all(|p| ids.contains(p)));
+        }
+    }
+}
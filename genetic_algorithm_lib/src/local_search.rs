@@ -0,0 +1,25 @@
+use crate::Member;
+
+/// How the result of local search is used
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LocalSearch {
+    /// The refined member replaces the original, i.e. learnt traits are inherited
+    Lamarckian,
+    /// The original member is kept, but is given the fitness of the refined member,
+    /// i.e. only the ability to learn is inherited
+    Baldwinian,
+}
+
+/// A member that can be improved by local search, e.g. greedy hill climbing
+pub trait Refine: Member + Sized {
+    /// Searches the neighbourhood of the member for a fitter member, using at most `budget`
+    /// fitness evaluations on top of evaluating the member itself. The neighbourhood is explored
+    /// with the mutations given by `breed_metadata`, e.g. so that small mutations fine tune.
+    /// Returns the fittest member found (which may be a copy of this member) and its fitness
+    fn refine(
+        &self,
+        metadata: &Self::FitnessMetadata,
+        breed_metadata: &Self::BreedMetadata,
+        budget: usize,
+    ) -> (Self, u64);
+}
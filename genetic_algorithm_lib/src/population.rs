@@ -1,4 +1,4 @@
-use crate::Member;
+use crate::{Constrained, ConstraintHandling, LocalSearch, Member, Refine};
 use rand::prelude::SliceRandom;
 use rand::{thread_rng, Rng};
 use std::borrow::Borrow;
@@ -31,6 +31,82 @@ impl<M: Member + Clone, const C: usize> Population<M, C> {
             member_fitness: members,
         }
     }
+
+    /// Evaluates the population, refining each member with local search before it is ranked.
+    /// `budget` is the number of extra fitness evaluations each member may use for refinement,
+    /// and local search uses the mutations of `breed_metadata`
+    pub fn run_memetic(
+        self,
+        metadata: &M::FitnessMetadata,
+        breed_metadata: &M::BreedMetadata,
+        mode: LocalSearch,
+        budget: usize,
+    ) -> EvaluatedPopulation<M, C>
+    where
+        M: Refine,
+    {
+        let mut members = self.members.map(|m| {
+            let (refined, f) = m.refine(metadata, breed_metadata, budget);
+            match mode {
+                LocalSearch::Lamarckian => (refined, f),
+                LocalSearch::Baldwinian => (m, f),
+            }
+        });
+        // Sorts by fitness
+        members.sort_by_key(|(_, f)| *f);
+        EvaluatedPopulation {
+            member_fitness: members,
+        }
+    }
+
+    /// Evaluates the population, handling any constraint violations according to `handling`
+    pub fn run_constrained(
+        self,
+        metadata: &M::FitnessMetadata,
+        handling: ConstraintHandling,
+    ) -> EvaluatedPopulation<M, C>
+    where
+        M: Constrained,
+    {
+        let mut members = self.members;
+        if handling == ConstraintHandling::Repair {
+            members.iter_mut().for_each(|m| m.repair(metadata));
+        }
+        let evaluations = members.each_ref().map(|m| {
+            let violation = m.violation(metadata);
+            // The death penalty doesn't need the fitness of infeasible members
+            let skip = violation > 0
+                && matches!(
+                    handling,
+                    ConstraintHandling::DeathPenalty | ConstraintHandling::Repair
+                );
+            (if skip { 0 } else { m.fitness(metadata) }, violation)
+        });
+
+        let mut fitness = handling.apply(evaluations).into_iter();
+        let mut members = members.map(|m| (m, fitness.next().unwrap()));
+        // Sorts by fitness
+        members.sort_by_key(|(_, f)| *f);
+        EvaluatedPopulation {
+            member_fitness: members,
+        }
+    }
+
+    /// Evaluates every member at once, `fitness` is given the members and returns the fitness of
+    /// each, in the same order. This is for evaluations that can't be expressed one member at a
+    /// time, e.g. farming the members out to worker processes
+    pub fn run_batch<F>(self, fitness: F) -> EvaluatedPopulation<M, C>
+    where
+        F: FnOnce(&[M; C]) -> [u64; C],
+    {
+        let mut fitness = fitness(&self.members).into_iter();
+        let mut members = self.members.map(|m| (m, fitness.next().unwrap()));
+        // Sorts by fitness
+        members.sort_by_key(|(_, f)| *f);
+        EvaluatedPopulation {
+            member_fitness: members,
+        }
+    }
 }
 
 pub struct EvaluatedPopulation<M: Member + Clone, const C: usize> {
@@ -50,10 +126,16 @@ impl<M: Member + Clone, const C: usize> EvaluatedPopulation<M, C> {
         )
     }
 
+    /// Gets every member of the population along with its fitness, sorted by fitness, smallest
+    /// first
+    pub fn members(&self) -> &[(M, u64); C] {
+        &self.member_fitness
+    }
+
     /// Gets the worst member of the population, along with its fitness
     pub fn worst(&self) -> (&M, u64) {
         // Members is sorted, so worst is the first element
-        (&self.member_fitness[0].0, self.member_fitness[1].1)
+        (&self.member_fitness[0].0, self.member_fitness[0].1)
     }
 
     /// Breeds the members of the population, according to their fitness, (natural selection),
@@ -64,15 +146,20 @@ impl<M: Member + Clone, const C: usize> EvaluatedPopulation<M, C> {
         let best_fitness = self.best().1;
         let worst_fitness = self.worst().1;
 
-        //Calculates the gradient to be used in the normalisation equation
-        let gradient = 9.0 / (best_fitness - worst_fitness) as f64;
+        //Calculates the gradient to be used in the normalisation equation, if every member is
+        // equally fit then they are all given the same weight
+        let gradient = if best_fitness == worst_fitness {
+            0.0
+        } else {
+            9.0 / (best_fitness - worst_fitness) as f64
+        };
         let mut mating_pool = Vec::new();
         // Creates the mating pool
         for i in 0..(C - 1) {
             let (member, fitness) = &self.member_fitness[i];
-            //Calculates the normalised fitness for the member, n = (f - s) * m + 10
+            //Calculates the normalised fitness for the member, n = (f - w) * m + 1
             let normalised_fitness =
-                (((fitness - best_fitness) as f64 * gradient + 1_f64) as u8).pow(2);
+                (((fitness - worst_fitness) as f64 * gradient + 1_f64) as u8).pow(2);
             // Adds the member into the mating pool based on its normalised fitness
             for i in 0..normalised_fitness {
                 mating_pool.push(member);
@@ -0,0 +1,109 @@
+use super::{BreedMetadata, Problem};
+use crate::Member;
+use rand::random;
+
+/// Breeds two bit strings using uniform crossover, each bit of the child is then flipped with
+/// probability `mutation_rate`
+fn breed_bits<const N: usize>(
+    left: &[bool; N],
+    right: &[bool; N],
+    mutation_rate: f32,
+) -> [bool; N] {
+    let mut i = 0;
+    [0; N].map(|_| {
+        let bit = if random() { left[i] } else { right[i] };
+        i += 1;
+        if random::<f32>() < mutation_rate {
+            !bit
+        } else {
+            bit
+        }
+    })
+}
+
+/// The OneMax problem, the fitness is the number of set bits, so the optimum is N
+#[derive(Clone, Debug, PartialEq)]
+pub struct OneMax<const N: usize> {
+    bits: [bool; N],
+}
+
+impl<const N: usize> OneMax<N> {
+    pub fn from_bits(bits: [bool; N]) -> Self {
+        OneMax { bits }
+    }
+
+    pub fn get_bits(&self) -> &[bool; N] {
+        &self.bits
+    }
+}
+
+impl<const N: usize> Member for OneMax<N> {
+    type FitnessMetadata = ();
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, _metadata: &Self::FitnessMetadata) -> u64 {
+        self.bits.iter().filter(|bit| **bit).count() as u64
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        OneMax {
+            bits: breed_bits(&left.bits, &right.bits, metadata.mutation_rate),
+        }
+    }
+}
+
+impl<const N: usize> Problem for OneMax<N> {
+    fn random(_metadata: &Self::FitnessMetadata) -> Self {
+        OneMax {
+            bits: [0; N].map(|_| random()),
+        }
+    }
+
+    fn optimum(_metadata: &Self::FitnessMetadata) -> u64 {
+        N as u64
+    }
+}
+
+/// The LeadingOnes problem, the fitness is the number of set bits before the first unset bit,
+/// so the optimum is N
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeadingOnes<const N: usize> {
+    bits: [bool; N],
+}
+
+impl<const N: usize> LeadingOnes<N> {
+    pub fn from_bits(bits: [bool; N]) -> Self {
+        LeadingOnes { bits }
+    }
+
+    pub fn get_bits(&self) -> &[bool; N] {
+        &self.bits
+    }
+}
+
+impl<const N: usize> Member for LeadingOnes<N> {
+    type FitnessMetadata = ();
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, _metadata: &Self::FitnessMetadata) -> u64 {
+        self.bits.iter().take_while(|bit| **bit).count() as u64
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        LeadingOnes {
+            bits: breed_bits(&left.bits, &right.bits, metadata.mutation_rate),
+        }
+    }
+}
+
+impl<const N: usize> Problem for LeadingOnes<N> {
+    fn random(_metadata: &Self::FitnessMetadata) -> Self {
+        LeadingOnes {
+            bits: [0; N].map(|_| random()),
+        }
+    }
+
+    fn optimum(_metadata: &Self::FitnessMetadata) -> u64 {
+        N as u64
+    }
+}
@@ -0,0 +1,130 @@
+use super::{BreedMetadata, Problem};
+use crate::Member;
+use rand::{random, Rng};
+use std::f64::consts::PI;
+use std::marker::PhantomData;
+
+/// The fitness of a genome whose objective value is 0, i.e. the global minimum
+const FITNESS_SCALE: u64 = 1_000_000_000;
+
+/// A continuous function to be minimised, the global minimum of every objective is 0
+pub trait Objective {
+    /// The lower and upper bound of every gene
+    const BOUNDS: (f64, f64);
+
+    /// Evaluates the objective at the point `genes`
+    fn evaluate(genes: &[f64]) -> f64;
+}
+
+/// f(x) = sum(x_i^2), minimum at x = 0
+#[derive(Clone, Debug, PartialEq)]
+pub struct SphereObjective;
+
+impl Objective for SphereObjective {
+    const BOUNDS: (f64, f64) = (-5.12, 5.12);
+
+    fn evaluate(genes: &[f64]) -> f64 {
+        genes.iter().map(|x| x * x).sum()
+    }
+}
+
+/// f(x) = 10n + sum(x_i^2 - 10cos(2 pi x_i)), minimum at x = 0
+#[derive(Clone, Debug, PartialEq)]
+pub struct RastriginObjective;
+
+impl Objective for RastriginObjective {
+    const BOUNDS: (f64, f64) = (-5.12, 5.12);
+
+    fn evaluate(genes: &[f64]) -> f64 {
+        10.0 * genes.len() as f64
+            + genes
+                .iter()
+                .map(|x| x * x - 10.0 * (2.0 * PI * x).cos())
+                .sum::<f64>()
+    }
+}
+
+/// f(x) = sum(100(x_i+1 - x_i^2)^2 + (1 - x_i)^2), minimum at x = 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct RosenbrockObjective;
+
+impl Objective for RosenbrockObjective {
+    const BOUNDS: (f64, f64) = (-2.048, 2.048);
+
+    fn evaluate(genes: &[f64]) -> f64 {
+        genes
+            .windows(2)
+            .map(|pair| 100.0 * (pair[1] - pair[0] * pair[0]).powi(2) + (1.0 - pair[0]).powi(2))
+            .sum()
+    }
+}
+
+pub type Sphere<const N: usize> = Continuous<SphereObjective, N>;
+pub type Rastrigin<const N: usize> = Continuous<RastriginObjective, N>;
+pub type Rosenbrock<const N: usize> = Continuous<RosenbrockObjective, N>;
+
+/// A real valued genome of N genes, minimising the objective O.
+/// The fitness is FITNESS_SCALE / (1 + f(x)), so the optimum is FITNESS_SCALE
+#[derive(Clone, Debug, PartialEq)]
+pub struct Continuous<O: Objective, const N: usize> {
+    genes: [f64; N],
+    objective: PhantomData<O>,
+}
+
+impl<O: Objective, const N: usize> Continuous<O, N> {
+    pub fn from_genes(genes: [f64; N]) -> Self {
+        Continuous {
+            genes,
+            objective: PhantomData,
+        }
+    }
+
+    pub fn get_genes(&self) -> &[f64; N] {
+        &self.genes
+    }
+}
+
+impl<O: Objective + Clone, const N: usize> Member for Continuous<O, N> {
+    type FitnessMetadata = ();
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, _metadata: &Self::FitnessMetadata) -> u64 {
+        (FITNESS_SCALE as f64 / (1.0 + O::evaluate(&self.genes))) as u64
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        let mut rng = rand::thread_rng();
+        let (lower, upper) = O::BOUNDS;
+        // Mutations move a gene by at most a tenth of its range
+        let step = (upper - lower) / 10.0;
+
+        let mut i = 0;
+        let genes = [0; N].map(|_| {
+            // Uniform crossover of the genes
+            let gene = if random() {
+                left.genes[i]
+            } else {
+                right.genes[i]
+            };
+            i += 1;
+            if random::<f32>() < metadata.mutation_rate {
+                (gene + rng.gen_range(-step..=step)).clamp(lower, upper)
+            } else {
+                gene
+            }
+        });
+        Continuous::from_genes(genes)
+    }
+}
+
+impl<O: Objective + Clone, const N: usize> Problem for Continuous<O, N> {
+    fn random(_metadata: &Self::FitnessMetadata) -> Self {
+        let mut rng = rand::thread_rng();
+        let (lower, upper) = O::BOUNDS;
+        Continuous::from_genes([0; N].map(|_| rng.gen_range(lower..=upper)))
+    }
+
+    fn optimum(_metadata: &Self::FitnessMetadata) -> u64 {
+        FITNESS_SCALE
+    }
+}
@@ -0,0 +1,47 @@
+use super::Problem;
+use crate::Population;
+
+/// The outcome of running a benchmark problem
+#[derive(Clone, Copy, Debug)]
+pub struct Report {
+    /// The number of generations that were evaluated
+    pub generations: usize,
+    /// The number of fitness evaluations that were performed
+    pub evaluations: u64,
+    /// The best fitness that was seen
+    pub best_fitness: u64,
+    /// Whether the best fitness reached the requested quality
+    pub reached: bool,
+}
+
+/// Evolves a random population of C members of the problem P until a member's fitness is at
+/// least `quality`, or `max_generations` generations have been evaluated.
+/// Use `P::optimum` to derive `quality`, e.g. the optimum itself, or 95% of it
+pub fn evaluations_to_quality<P: Problem, const C: usize>(
+    fitness_metadata: &P::FitnessMetadata,
+    breed_metadata: &P::BreedMetadata,
+    quality: u64,
+    max_generations: usize,
+) -> Report {
+    let mut population = Population::new([0; C].map(|_| P::random(fitness_metadata)));
+    let mut report = Report {
+        generations: 0,
+        evaluations: 0,
+        best_fitness: 0,
+        reached: false,
+    };
+
+    while report.generations < max_generations {
+        let evaluated = population.run(fitness_metadata);
+        report.generations += 1;
+        report.evaluations += C as u64;
+        report.best_fitness = report.best_fitness.max(evaluated.best().1);
+
+        if report.best_fitness >= quality {
+            report.reached = true;
+            break;
+        }
+        population = evaluated.breed(breed_metadata);
+    }
+    report
+}
@@ -0,0 +1,106 @@
+use super::{BreedMetadata, Problem};
+use crate::Member;
+use rand::prelude::SliceRandom;
+use rand::random;
+
+/// A 0/1 knapsack instance with N items
+pub struct KnapsackInstance<const N: usize> {
+    weights: [u64; N],
+    values: [u64; N],
+    capacity: u64,
+}
+
+impl<const N: usize> KnapsackInstance<N> {
+    pub fn new(weights: [u64; N], values: [u64; N], capacity: u64) -> Self {
+        KnapsackInstance {
+            weights,
+            values,
+            capacity,
+        }
+    }
+
+    /// The best achievable value, found with dynamic programming over the capacity
+    pub fn optimal_value(&self) -> u64 {
+        // best[c] is the best value achievable with a capacity of c
+        let mut best = vec![0_u64; self.capacity as usize + 1];
+        for i in 0..N {
+            let weight = self.weights[i] as usize;
+            // Iterates downwards so that each item is only taken once
+            for c in (weight..best.len()).rev() {
+                best[c] = best[c].max(best[c - weight] + self.values[i]);
+            }
+        }
+        best[self.capacity as usize]
+    }
+}
+
+/// A selection of items from a KnapsackInstance, the fitness is the total value of the selection,
+/// or 0 if the selection exceeds the capacity
+#[derive(Clone, Debug, PartialEq)]
+pub struct Knapsack<const N: usize> {
+    items: [bool; N],
+}
+
+impl<const N: usize> Knapsack<N> {
+    pub fn from_items(items: [bool; N]) -> Self {
+        Knapsack { items }
+    }
+
+    pub fn get_items(&self) -> &[bool; N] {
+        &self.items
+    }
+}
+
+impl<const N: usize> Member for Knapsack<N> {
+    type FitnessMetadata = KnapsackInstance<N>;
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, metadata: &Self::FitnessMetadata) -> u64 {
+        let (mut weight, mut value) = (0, 0);
+        for i in (0..N).filter(|i| self.items[*i]) {
+            weight += metadata.weights[i];
+            value += metadata.values[i];
+        }
+        if weight > metadata.capacity {
+            0
+        } else {
+            value
+        }
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        let mut i = 0;
+        let items = [0; N].map(|_| {
+            let item = if random() {
+                left.items[i]
+            } else {
+                right.items[i]
+            };
+            i += 1;
+            item ^ (random::<f32>() < metadata.mutation_rate)
+        });
+        Knapsack { items }
+    }
+}
+
+impl<const N: usize> Problem for Knapsack<N> {
+    /// Adds items in a random order whilst they fit, so that the initial member is feasible
+    fn random(metadata: &Self::FitnessMetadata) -> Self {
+        let mut order: Vec<usize> = (0..N).collect();
+        order.shuffle(&mut rand::thread_rng());
+
+        let mut items = [false; N];
+        let mut weight = 0;
+        for i in order {
+            if weight + metadata.weights[i] <= metadata.capacity && random() {
+                weight += metadata.weights[i];
+                items[i] = true;
+            }
+        }
+        Knapsack { items }
+    }
+
+    fn optimum(metadata: &Self::FitnessMetadata) -> u64 {
+        metadata.optimal_value()
+    }
+}
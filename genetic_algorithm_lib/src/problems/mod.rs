@@ -0,0 +1,91 @@
+use crate::Member;
+
+mod bits;
+pub use bits::{LeadingOnes, OneMax};
+
+mod continuous;
+pub use continuous::{Continuous, Objective, Rastrigin, Rosenbrock, Sphere};
+
+mod knapsack;
+pub use knapsack::{Knapsack, KnapsackInstance};
+
+mod tsp;
+pub use tsp::{Tour, TspInstance};
+
+mod harness;
+pub use harness::{evaluations_to_quality, Report};
+
+/// A benchmark problem, i.e. a member with a known optimum, so that changes to the genetic
+/// algorithm can be measured independently of the image pipeline
+pub trait Problem: Member + Clone {
+    /// Creates a random member of the problem's search space
+    fn random(metadata: &Self::FitnessMetadata) -> Self;
+
+    /// The fitness of the optimal member of the problem
+    fn optimum(metadata: &Self::FitnessMetadata) -> u64;
+}
+
+/// The breed metadata shared by all of the benchmark problems
+pub struct BreedMetadata {
+    /// The probability of mutating each gene of a child
+    mutation_rate: f32,
+}
+
+impl BreedMetadata {
+    pub fn new(mutation_rate: f32) -> Self {
+        BreedMetadata { mutation_rate }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optimal_members_score_the_optimum() {
+        let one_max = OneMax::<16>::from_bits([true; 16]);
+        assert_eq!(one_max.fitness(&()), OneMax::<16>::optimum(&()));
+
+        let leading_ones = LeadingOnes::<16>::from_bits([true; 16]);
+        assert_eq!(leading_ones.fitness(&()), LeadingOnes::<16>::optimum(&()));
+
+        let sphere = Sphere::<4>::from_genes([0.0; 4]);
+        assert_eq!(sphere.fitness(&()), Sphere::<4>::optimum(&()));
+
+        let rosenbrock = Rosenbrock::<4>::from_genes([1.0; 4]);
+        assert_eq!(rosenbrock.fitness(&()), Rosenbrock::<4>::optimum(&()));
+
+        let instance = TspInstance::<12>::regular_polygon(1000.0);
+        let tour = Tour::<12>::from_order([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(tour.fitness(&instance), Tour::<12>::optimum(&instance));
+    }
+
+    #[test]
+    fn knapsack_optimum_matches_brute_force() {
+        let instance = KnapsackInstance::new(
+            [12, 7, 11, 8, 9, 6, 5, 14],
+            [24, 13, 23, 15, 16, 11, 9, 30],
+            26,
+        );
+        let best = (0..(1_u32 << 8))
+            .map(|mask| {
+                let mut items = [false; 8];
+                for (i, item) in items.iter_mut().enumerate() {
+                    *item = mask & (1 << i) != 0;
+                }
+                Knapsack::from_items(items).fitness(&instance)
+            })
+            .max()
+            .unwrap();
+        assert_eq!(best, Knapsack::<8>::optimum(&instance));
+    }
+
+    #[test]
+    fn harness_counts_evaluations_per_generation() {
+        let breed_metadata = BreedMetadata::new(0.05);
+        let report = evaluations_to_quality::<OneMax<32>, 10>(&(), &breed_metadata, u64::MAX, 5);
+        assert!(!report.reached);
+        assert_eq!(report.generations, 5);
+        assert_eq!(report.evaluations, 50);
+    }
+}
@@ -0,0 +1,175 @@
+use super::{BreedMetadata, Problem};
+use crate::Member;
+use rand::prelude::SliceRandom;
+use rand::{random, Rng};
+use std::f64::consts::PI;
+
+/// A symmetric travelling salesman instance with N cities, distances are rounded to the nearest
+/// integer (as in TSPLIB's EUC_2D) so that tour lengths are exact
+pub struct TspInstance<const N: usize> {
+    /// distances[i][j] is the distance between city i and city j
+    distances: [[u64; N]; N],
+    /// The length of the shortest tour
+    optimal_length: u64,
+    /// An upper bound on the length of any tour, used to convert lengths into fitness
+    upper_bound: u64,
+}
+
+impl<const N: usize> TspInstance<N> {
+    /// Creates an instance from city coordinates, the optimal tour is found exactly with the
+    /// Held-Karp algorithm, so this is only intended for small instances (N <= 16)
+    pub fn new(cities: [(f64, f64); N]) -> Self {
+        let distances = Self::distances(&cities);
+        let optimal_length = Self::held_karp(&distances);
+        Self::from_parts(distances, optimal_length)
+    }
+
+    /// Creates an instance with the cities placed on the corners of a regular polygon, the
+    /// optimal tour visits them in order around the polygon
+    pub fn regular_polygon(radius: f64) -> Self {
+        let mut i = 0;
+        let cities = [0; N].map(|_| {
+            let angle = 2.0 * PI * i as f64 / N as f64;
+            i += 1;
+            (radius * angle.cos(), radius * angle.sin())
+        });
+        let distances = Self::distances(&cities);
+        let optimal_length = (0..N).map(|i| distances[i][(i + 1) % N]).sum();
+        Self::from_parts(distances, optimal_length)
+    }
+
+    fn from_parts(distances: [[u64; N]; N], optimal_length: u64) -> Self {
+        let longest = distances.iter().flatten().max().copied().unwrap_or(0);
+        TspInstance {
+            distances,
+            optimal_length,
+            upper_bound: longest * N as u64,
+        }
+    }
+
+    fn distances(cities: &[(f64, f64); N]) -> [[u64; N]; N] {
+        let mut distances = [[0; N]; N];
+        for i in 0..N {
+            for j in 0..N {
+                let (dx, dy) = (cities[i].0 - cities[j].0, cities[i].1 - cities[j].1);
+                distances[i][j] = (dx * dx + dy * dy).sqrt().round() as u64;
+            }
+        }
+        distances
+    }
+
+    /// Finds the length of the shortest tour by dynamic programming over subsets of cities
+    fn held_karp(distances: &[[u64; N]; N]) -> u64 {
+        if N < 2 {
+            return 0;
+        }
+        // best[set][last] is the shortest path starting at city 0, visiting every city in `set`
+        // and ending at `last`; city 0 is never in `set`
+        let subsets = 1 << (N - 1);
+        let mut best = vec![[u64::MAX; N]; subsets];
+        for last in 1..N {
+            best[1 << (last - 1)][last] = distances[0][last];
+        }
+        for set in 1..subsets {
+            for last in (1..N).filter(|last| set & (1 << (last - 1)) != 0) {
+                let length = best[set][last];
+                if length == u64::MAX {
+                    continue;
+                }
+                for next in (1..N).filter(|next| set & (1 << (next - 1)) == 0) {
+                    let extended = set | (1 << (next - 1));
+                    best[extended][next] = best[extended][next].min(length + distances[last][next]);
+                }
+            }
+        }
+        (1..N)
+            .map(|last| best[subsets - 1][last] + distances[last][0])
+            .min()
+            .unwrap()
+    }
+
+    pub fn get_optimal_length(&self) -> u64 {
+        self.optimal_length
+    }
+}
+
+/// A tour of the N cities of a TspInstance, the fitness is the instance's upper bound minus the
+/// tour length, so shorter tours are fitter
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tour<const N: usize> {
+    order: [usize; N],
+}
+
+impl<const N: usize> Tour<N> {
+    pub fn from_order(order: [usize; N]) -> Self {
+        Tour { order }
+    }
+
+    pub fn get_order(&self) -> &[usize; N] {
+        &self.order
+    }
+
+    pub fn length(&self, instance: &TspInstance<N>) -> u64 {
+        (0..N)
+            .map(|i| instance.distances[self.order[i]][self.order[(i + 1) % N]])
+            .sum()
+    }
+}
+
+impl<const N: usize> Member for Tour<N> {
+    type FitnessMetadata = TspInstance<N>;
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, metadata: &Self::FitnessMetadata) -> u64 {
+        metadata.upper_bound - self.length(metadata)
+    }
+
+    /// Breeds using order crossover (OX1), then applies inversion mutations
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        let mut rng = rand::thread_rng();
+        if N < 2 {
+            return left.clone();
+        }
+
+        // Copies a random segment of the left parent, the remaining cities are taken in the
+        // order that they appear in the right parent
+        let start = rng.gen_range(0..N);
+        let end = rng.gen_range(start..=N);
+        let mut order = [0; N];
+        let mut used = [false; N];
+        for i in start..end {
+            order[i] = left.order[i];
+            used[left.order[i]] = true;
+        }
+        let mut remaining = right.order.iter().filter(|city| !used[**city]);
+        for i in (0..start).chain(end..N) {
+            order[i] = *remaining.next().unwrap();
+        }
+
+        // Each city has a `mutation_rate` chance of starting a reversed segment
+        for _ in 0..N {
+            if random::<f32>() < metadata.mutation_rate {
+                let first = rng.gen_range(0..N);
+                let second = rng.gen_range(0..N);
+                order[first.min(second)..=first.max(second)].reverse();
+            }
+        }
+        Tour { order }
+    }
+}
+
+impl<const N: usize> Problem for Tour<N> {
+    fn random(_metadata: &Self::FitnessMetadata) -> Self {
+        let mut i = 0;
+        let mut order = [0; N].map(|_| {
+            i += 1;
+            i - 1
+        });
+        order.shuffle(&mut rand::thread_rng());
+        Tour { order }
+    }
+
+    fn optimum(metadata: &Self::FitnessMetadata) -> u64 {
+        metadata.upper_bound - metadata.optimal_length
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{EvaluatedPopulation, Member, Population};
+
+mod wire;
+pub use wire::{read_frame, write_frame, Decode, Encode, MAX_FRAME_LEN};
+
+mod pool;
+pub use pool::{serve, WorkerAddress, WorkerPool};
+
+impl<M: Member + Clone + Encode, const C: usize> Population<M, C>
+where
+    M::FitnessMetadata: Encode,
+{
+    /// Evaluates the population on a pool of out-of-process workers, any member that no worker
+    /// was able to evaluate is evaluated locally instead
+    pub fn run_distributed(
+        self,
+        metadata: &M::FitnessMetadata,
+        pool: &mut WorkerPool,
+    ) -> EvaluatedPopulation<M, C> {
+        self.run_batch(|members| {
+            let mut encoded_metadata = Vec::new();
+            metadata.encode(&mut encoded_metadata);
+            let genomes: Vec<Vec<u8>> = members
+                .iter()
+                .map(|m| {
+                    let mut genome = Vec::new();
+                    m.encode(&mut genome);
+                    genome
+                })
+                .collect();
+
+            let fitness = pool.evaluate(&encoded_metadata, &genomes);
+            let mut i = 0;
+            [0; C].map(|_| {
+                i += 1;
+                fitness[i - 1].unwrap_or_else(|| members[i - 1].fitness(metadata))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_survive_the_wire() {
+        let mut bytes = Vec::new();
+        [(3_u16, true), (65535, false)].encode(&mut bytes);
+        vec![7_u64, 9].encode(&mut bytes);
+        0.5_f32.encode(&mut bytes);
+
+        let mut input = bytes.as_slice();
+        assert_eq!(
+            <[(u16, bool); 2]>::decode(&mut input),
+            Some([(3, true), (65535, false)])
+        );
+        assert_eq!(Vec::<u64>::decode(&mut input), Some(vec![7, 9]));
+        assert_eq!(f32::decode(&mut input), Some(0.5));
+        assert!(input.is_empty());
+        assert_eq!(u8::decode(&mut input), None);
+    }
+
+    #[test]
+    fn serve_answers_each_request() {
+        // Builds two requests, the "genome" is a u64 that the worker doubles
+        let mut requests = Vec::new();
+        for (job, genome) in [(0_u64, 21_u64), (1, 5)] {
+            let mut request = Vec::new();
+            job.encode(&mut request);
+            0_u32.encode(&mut request);
+            genome.encode(&mut request);
+            write_frame(&mut requests, &request).unwrap();
+        }
+
+        let mut responses = Vec::new();
+        serve(requests.as_slice(), &mut responses, |genome, _| {
+            Some(u64::decode(genome)? * 2)
+        })
+        .unwrap();
+
+        let mut responses = responses.as_slice();
+        for (job, fitness) in [(0_u64, 42_u64), (1, 10)] {
+            let response = read_frame(&mut responses).unwrap().unwrap();
+            let mut response = response.as_slice();
+            assert_eq!(u64::decode(&mut response), Some(job));
+            assert_eq!(u64::decode(&mut response), Some(fitness));
+        }
+        assert_eq!(read_frame(&mut responses).unwrap(), None);
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let header = (MAX_FRAME_LEN as u32 + 1).to_le_bytes();
+        let error = read_frame(&mut header.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,293 @@
+use super::wire::{read_frame, write_frame, Decode, Encode};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Where a fitness worker can be reached
+#[derive(Clone, Debug)]
+pub enum WorkerAddress {
+    /// A child process that is spawned by the pool, it is sent requests on its stdin and
+    /// replies on its stdout
+    Process { program: PathBuf, args: Vec<String> },
+    /// A worker listening on a TCP socket, e.g. "127.0.0.1:7878"
+    Tcp(String),
+    /// A worker listening on a Unix domain socket
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// An open connection to a worker
+struct Connection {
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    /// The worker's process, if the pool spawned it
+    child: Option<Child>,
+}
+
+impl Connection {
+    /// Opens a connection on which every read and write fails if it takes longer than `timeout`
+    fn open(address: &WorkerAddress, timeout: Duration) -> io::Result<Connection> {
+        match address {
+            WorkerAddress::Process { program, args } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()?;
+                Ok(Connection {
+                    writer: Box::new(PipeWriter::new(child.stdin.take().unwrap())),
+                    reader: Box::new(PipeReader::new(child.stdout.take().unwrap(), timeout)),
+                    child: Some(child),
+                })
+            }
+            WorkerAddress::Tcp(address) => {
+                let stream = TcpStream::connect(address)?;
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                Ok(Connection {
+                    reader: Box::new(stream.try_clone()?),
+                    writer: Box::new(stream),
+                    child: None,
+                })
+            }
+            #[cfg(unix)]
+            WorkerAddress::Unix(path) => {
+                let stream = std::os::unix::net::UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                Ok(Connection {
+                    reader: Box::new(stream.try_clone()?),
+                    writer: Box::new(stream),
+                    child: None,
+                })
+            }
+        }
+    }
+
+    /// Sends a single job and waits for its fitness
+    fn evaluate(&mut self, job: u64, metadata: &[u8], genome: &[u8]) -> io::Result<u64> {
+        let mut request = Vec::with_capacity(12 + metadata.len() + genome.len());
+        job.encode(&mut request);
+        (metadata.len() as u32).encode(&mut request);
+        request.extend_from_slice(metadata);
+        request.extend_from_slice(genome);
+        write_frame(&mut self.writer, &request)?;
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed worker response");
+        let response = read_frame(&mut self.reader)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "worker disconnected"))?;
+        let mut response = response.as_slice();
+        if u64::decode(&mut response).ok_or_else(invalid)? != job {
+            return Err(invalid());
+        }
+        u64::decode(&mut response).ok_or_else(invalid)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            // The worker may have already exited, in which case there is nothing to clean up
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Reads from a pipe on a background thread, so that reads can time out, which pipes don't
+/// support themselves
+struct PipeReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    /// The part of the last chunk that hasn't been read yet
+    pending: VecDeque<u8>,
+    timeout: Duration,
+}
+
+impl PipeReader {
+    fn new<R: Read + Send + 'static>(mut pipe: R, timeout: Duration) -> PipeReader {
+        let (sender, chunks) = channel();
+        // The thread finishes when the pipe is closed, i.e. when the worker exits
+        std::thread::spawn(move || {
+            let mut buffer = [0; 8192];
+            loop {
+                let chunk = match pipe.read(&mut buffer) {
+                    Ok(0) => return,
+                    Ok(n) => Ok(buffer[..n].to_vec()),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let failed = chunk.is_err();
+                if sender.send(chunk).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        PipeReader {
+            chunks,
+            pending: VecDeque::new(),
+            timeout,
+        }
+    }
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.chunks.recv_timeout(self.timeout) {
+                Ok(chunk) => self.pending.extend(chunk?),
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::TimedOut.into()),
+                // The pipe was closed
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        for (byte, pending) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *byte = pending;
+        }
+        Ok(n)
+    }
+}
+
+/// Writes to a pipe on a background thread, so that a worker which stops reading can't block
+/// the pool, the stalled worker is instead caught by the timeout on reading its response
+struct PipeWriter {
+    chunks: Sender<Vec<u8>>,
+}
+
+impl PipeWriter {
+    fn new<W: Write + Send + 'static>(mut pipe: W) -> PipeWriter {
+        let (chunks, receiver) = channel::<Vec<u8>>();
+        // The thread finishes when the writer is dropped or the pipe is closed
+        std::thread::spawn(move || {
+            for chunk in receiver {
+                if pipe.write_all(&chunk).and_then(|_| pipe.flush()).is_err() {
+                    return;
+                }
+            }
+        });
+        PipeWriter { chunks }
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.chunks
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A set of fitness workers, connections are kept open between generations and are reopened
+/// (or the worker respawned) if the worker fails
+pub struct WorkerPool {
+    workers: Vec<(WorkerAddress, Option<Connection>)>,
+    /// The number of times a worker may be reconnected to during a single evaluation before
+    /// it is abandoned for the rest of that evaluation
+    max_restarts: usize,
+    /// How long a worker may take to accept a request or to reply, before it is treated as
+    /// having failed
+    timeout: Duration,
+}
+
+impl WorkerPool {
+    pub fn new(addresses: Vec<WorkerAddress>) -> WorkerPool {
+        WorkerPool {
+            workers: addresses.into_iter().map(|a| (a, None)).collect(),
+            max_restarts: 3,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn set_max_restarts(&mut self, max_restarts: usize) {
+        self.max_restarts = max_restarts;
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Evaluates every genome against the (encoded) fitness metadata, the i'th result is the
+    /// fitness of the i'th genome, or None if no worker was able to evaluate it
+    pub fn evaluate(&mut self, metadata: &[u8], genomes: &[Vec<u8>]) -> Vec<Option<u64>> {
+        let queue = Mutex::new((0..genomes.len()).collect::<VecDeque<usize>>());
+        let results = Mutex::new(vec![None; genomes.len()]);
+        let (max_restarts, timeout) = (self.max_restarts, self.timeout);
+
+        std::thread::scope(|scope| {
+            for (address, connection) in self.workers.iter_mut() {
+                let (queue, results) = (&queue, &results);
+                scope.spawn(move || {
+                    let mut restarts = 0;
+                    loop {
+                        let job = match queue.lock().unwrap().pop_front() {
+                            Some(job) => job,
+                            None => return,
+                        };
+                        // Connects lazily, so that unreachable workers don't hold up the pool
+                        if connection.is_none() {
+                            *connection = Connection::open(address, timeout).ok();
+                        }
+                        let result = match connection {
+                            Some(c) => c.evaluate(job as u64, metadata, &genomes[job]),
+                            None => Err(io::Error::from(io::ErrorKind::NotConnected)),
+                        };
+                        match result {
+                            Ok(fitness) => results.lock().unwrap()[job] = Some(fitness),
+                            Err(_) => {
+                                // The worker crashed, disconnected or timed out, so the job is
+                                // re-queued for any worker and this one is restarted
+                                queue.lock().unwrap().push_back(job);
+                                *connection = None;
+                                restarts += 1;
+                                if restarts > max_restarts {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        results.into_inner().unwrap()
+    }
+}
+
+/// Runs a fitness worker, answering requests from `reader` on `writer` until the stream is
+/// closed.
+/// `evaluate` is given the encoded genome and fitness metadata of each request, it should
+/// return the member's fitness, or None if the request couldn't be decoded
+pub fn serve<R, W, F>(mut reader: R, mut writer: W, mut evaluate: F) -> io::Result<()>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&mut &[u8], &mut &[u8]) -> Option<u64>,
+{
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed worker request");
+    while let Some(request) = read_frame(&mut reader)? {
+        let mut request = request.as_slice();
+        let job = u64::decode(&mut request).ok_or_else(invalid)?;
+        let metadata_len = u32::decode(&mut request).ok_or_else(invalid)? as usize;
+        if request.len() < metadata_len {
+            return Err(invalid());
+        }
+        let (mut metadata, mut genome) = request.split_at(metadata_len);
+        let fitness = evaluate(&mut genome, &mut metadata).ok_or_else(invalid)?;
+
+        let mut response = Vec::with_capacity(16);
+        job.encode(&mut response);
+        fitness.encode(&mut response);
+        write_frame(&mut writer, &response)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+/// The largest frame that may be sent or received, so that a corrupt or malicious length can't
+/// force a huge allocation
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// A value that can be serialised to send to a fitness worker
+pub trait Encode {
+    /// Appends the little endian representation of the value to `out`
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// A value that can be deserialised by a fitness worker
+pub trait Decode: Sized {
+    /// Reads the value from the front of `input`, advancing it past the value.
+    /// None is returned if `input` doesn't contain a valid value
+    fn decode(input: &mut &[u8]) -> Option<Self>;
+}
+
+/// Splits the first `n` bytes off `input`
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (taken, rest) = input.split_at(n);
+    *input = rest;
+    Some(taken)
+}
+
+macro_rules! impl_wire_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $t {
+                fn decode(input: &mut &[u8]) -> Option<Self> {
+                    let bytes = take(input, std::mem::size_of::<$t>())?;
+                    Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_wire_for_int!(u8, u16, u32, u64, f32, f64);
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u8).encode(out);
+    }
+}
+
+impl Decode for bool {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        for value in self {
+            value.encode(out);
+        }
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let values = [0; N].map(|_| T::decode(input));
+        if values.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(values.map(Option::unwrap))
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some((A::decode(input)?, B::decode(input)?))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).encode(out);
+        for value in self {
+            value.encode(out);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = u32::decode(input)?;
+        (0..len).map(|_| T::decode(input)).collect()
+    }
+}
+
+/// Writes a length prefixed frame, frames longer than `MAX_FRAME_LEN` aren't written
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> Result<()> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(Error::new(ErrorKind::InvalidInput, "frame is too long"));
+    }
+    writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Reads a length prefixed frame, None is returned if the stream was closed between frames.
+/// A frame longer than `MAX_FRAME_LEN` is an error
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    // Distinguishes a clean shutdown from a frame that was cut short
+    loop {
+        match reader.read(&mut len[..1]) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    reader.read_exact(&mut len[1..])?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(Error::new(ErrorKind::InvalidData, "frame is too long"));
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
@@ -1,13 +1,717 @@
+use genetic_algorithm_lib::workers::{Decode, Encode};
+use rand::Rng;
+use std::str::FromStr;
+
+/// A colour with straight (non-premultiplied) alpha, an alpha of 255 is opaque
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Colour {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+    pub a: u8,
 }
 
 impl Colour {
-    /// Creates a new colour with corresponding parameters
+    /// Creates a new opaque colour with corresponding parameters
     pub fn new(r: u8, g: u8, b: u8) -> Colour {
-        Colour { r, g, b }
+        Colour { r, g, b, a: 255 }
+    }
+
+    /// Creates a new colour with corresponding parameters, including its alpha
+    pub fn new_rgba(r: u8, g: u8, b: u8, a: u8) -> Colour {
+        Colour { r, g, b, a }
+    }
+
+    /// A fully transparent colour, the background of a transparent render
+    pub fn transparent() -> Colour {
+        Colour::new_rgba(0, 0, 0, 0)
+    }
+
+    /// Gets the red, green and blue channels multiplied by the alpha, i.e. the contribution
+    /// that this colour makes when it is composited
+    pub fn premultiplied(&self) -> (u8, u8, u8) {
+        let scale = |channel: u8| ((channel as u32 * self.a as u32 + 127) / 255) as u8;
+        (scale(self.r), scale(self.g), scale(self.b))
+    }
+
+    /// Composites this colour over `background` with the Porter-Duff source over operator
+    pub fn blend_over(&self, background: Colour) -> Colour {
+        let (a, b_a) = (self.a as u32, background.a as u32);
+        // The alpha of the result, scaled by 255
+        let alpha = a * 255 + b_a * (255 - a);
+        if alpha == 0 {
+            return Colour::transparent();
+        }
+        let blend = |channel: u8, b_channel: u8| {
+            let value = channel as u32 * a * 255 + b_channel as u32 * b_a * (255 - a);
+            ((value + alpha / 2) / alpha) as u8
+        };
+        Colour {
+            r: blend(self.r, background.r),
+            g: blend(self.g, background.g),
+            b: blend(self.b, background.b),
+            a: ((alpha + 127) / 255) as u8,
+        }
+    }
+}
+
+/// Colour conversions, these ignore the alpha channel and colours converted back to RGB are opaque
+impl Colour {
+    /// Converts to linear RGB, each channel is in `0..=1`
+    fn to_linear(self) -> [f64; 3] {
+        [self.r, self.g, self.b].map(|channel| {
+            let c = channel as f64 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    }
+
+    /// Converts from linear RGB, channels are clamped to the sRGB gamut
+    fn from_linear(linear: [f64; 3]) -> Colour {
+        let [r, g, b] = linear.map(|c| {
+            let c = if c <= 0.0031308 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (c.clamp(0.0, 1.0) * 255.0).round() as u8
+        });
+        Colour::new(r, g, b)
+    }
+
+    /// Converts to CIELAB, relative to the D65 white point
+    pub fn to_lab(&self) -> Lab {
+        let [r, g, b] = self.to_linear();
+        let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / 1.08883;
+        let f = |t: f64| {
+            if t > (6.0_f64 / 29.0).powi(3) {
+                t.cbrt()
+            } else {
+                t / (3.0 * (6.0_f64 / 29.0).powi(2)) + 4.0 / 29.0
+            }
+        };
+        Lab {
+            l: 116.0 * f(y) - 16.0,
+            a: 500.0 * (f(x) - f(y)),
+            b: 200.0 * (f(y) - f(z)),
+        }
+    }
+
+    pub fn from_lab(lab: Lab) -> Colour {
+        let f_y = (lab.l + 16.0) / 116.0;
+        let f_inverse = |t: f64| {
+            if t > 6.0 / 29.0 {
+                t.powi(3)
+            } else {
+                3.0 * (6.0_f64 / 29.0).powi(2) * (t - 4.0 / 29.0)
+            }
+        };
+        let x = f_inverse(f_y + lab.a / 500.0) * 0.95047;
+        let y = f_inverse(f_y);
+        let z = f_inverse(f_y - lab.b / 200.0) * 1.08883;
+        Colour::from_linear([
+            3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+            -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+            0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+        ])
+    }
+
+    /// Converts to OKLab, see https://bottosson.github.io/posts/oklab/
+    pub fn to_oklab(&self) -> OkLab {
+        let [r, g, b] = self.to_linear();
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        OkLab {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+
+    pub fn from_oklab(oklab: OkLab) -> Colour {
+        let l = (oklab.l + 0.3963377774 * oklab.a + 0.2158037573 * oklab.b).powi(3);
+        let m = (oklab.l - 0.1055613458 * oklab.a - 0.0638541728 * oklab.b).powi(3);
+        let s = (oklab.l - 0.0894841775 * oklab.a - 1.2914855480 * oklab.b).powi(3);
+        Colour::from_linear([
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ])
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f64 / 255.0);
+        let max = r.max(g).max(b);
+        let range = max - r.min(g).min(b);
+        let h = if range == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / range).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / range + 2.0)
+        } else {
+            60.0 * ((r - g) / range + 4.0)
+        };
+        Hsv {
+            h,
+            s: if max == 0.0 { 0.0 } else { range / max },
+            v: max,
+        }
+    }
+
+    pub fn from_hsv(hsv: Hsv) -> Colour {
+        let (s, v) = (hsv.s.clamp(0.0, 1.0), hsv.v.clamp(0.0, 1.0));
+        let h = hsv.h.rem_euclid(360.0) / 60.0;
+        let chroma = v * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = v - chroma;
+        let [r, g, b] = [r, g, b].map(|c| ((c + m) * 255.0).round() as u8);
+        Colour::new(r, g, b)
+    }
+}
+
+/// A colour in CIELAB, L is in `0..=100`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Lab {
+    /// The CIE76 colour difference, the euclidean distance between the colours
+    pub fn delta_e_cie76(&self, other: &Lab) -> f64 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+
+    /// The CIEDE2000 colour difference, as given by Sharma, Wu and Dalal (2005)
+    pub fn delta_e_ciede2000(&self, other: &Lab) -> f64 {
+        let (l1, a1, b1) = (self.l, self.a, self.b);
+        let (l2, a2, b2) = (other.l, other.a, other.b);
+        let pow7 = |x: f64| x.powi(7);
+
+        // Adjusts a* so that neutral colours have a hue of 0
+        let c_mean = ((a1 * a1 + b1 * b1).sqrt() + (a2 * a2 + b2 * b2).sqrt()) / 2.0;
+        let g = 0.5 * (1.0 - (pow7(c_mean) / (pow7(c_mean) + pow7(25.0))).sqrt());
+        let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+        let (c1, c2) = ((a1 * a1 + b1 * b1).sqrt(), (a2 * a2 + b2 * b2).sqrt());
+        let hue = |b: f64, a: f64| {
+            if a == 0.0 && b == 0.0 {
+                0.0
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.0)
+            }
+        };
+        let (h1, h2) = (hue(b1, a1), hue(b2, a2));
+
+        // The differences in lightness, chroma and hue
+        let delta_l = l2 - l1;
+        let delta_c = c2 - c1;
+        let delta_h = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= 180.0 {
+            h2 - h1
+        } else if h2 - h1 > 180.0 {
+            h2 - h1 - 360.0
+        } else {
+            h2 - h1 + 360.0
+        };
+        let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h.to_radians() / 2.0).sin();
+
+        // The means, which the weighting functions are based on
+        let l_mean = (l1 + l2) / 2.0;
+        let c_mean = (c1 + c2) / 2.0;
+        let h_mean = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+        let cos = |degrees: f64| degrees.to_radians().cos();
+        let t = 1.0 - 0.17 * cos(h_mean - 30.0)
+            + 0.24 * cos(2.0 * h_mean)
+            + 0.32 * cos(3.0 * h_mean + 6.0)
+            - 0.20 * cos(4.0 * h_mean - 63.0);
+        let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+        let r_c = 2.0 * (pow7(c_mean) / (pow7(c_mean) + pow7(25.0))).sqrt();
+        let s_l = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+        let s_c = 1.0 + 0.045 * c_mean;
+        let s_h = 1.0 + 0.015 * c_mean * t;
+        let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+        ((delta_l / s_l).powi(2)
+            + (delta_c / s_c).powi(2)
+            + (delta_h / s_h).powi(2)
+            + r_t * (delta_c / s_c) * (delta_h / s_h))
+            .sqrt()
+    }
+}
+
+/// A colour in OKLab, L is in `0..=1`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct OkLab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// A colour in HSV, the hue is in degrees and the saturation and value are in `0..=1`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+/// The colour space that colours are mutated in
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ColourSpace {
+    Rgb,
+    Lab,
+    OkLab,
+    Hsv,
+}
+
+impl FromStr for ColourSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rgb" => Ok(ColourSpace::Rgb),
+            "lab" => Ok(ColourSpace::Lab),
+            "oklab" => Ok(ColourSpace::OkLab),
+            "hsv" => Ok(ColourSpace::Hsv),
+            _ => Err(format!("unknown colour space `{}`", s)),
+        }
+    }
+}
+
+impl Colour {
+    /// Perturbs the colour by a small random amount in each channel of `space`, and its alpha by
+    /// up to 20
+    pub fn perturb(&self, space: ColourSpace) -> Colour {
+        let mut thread_rng = rand::thread_rng();
+        let a = perturb_channel(&mut thread_rng, self.a);
+
+        // Perceptual channels are perturbed by roughly the same proportion of their range as
+        // the RGB channels are
+        let mut perturb = |value: f64, amount: f64| value + thread_rng.gen_range(-amount..=amount);
+        let perturbed = match space {
+            ColourSpace::Rgb => Colour::new(
+                perturb_channel(&mut thread_rng, self.r),
+                perturb_channel(&mut thread_rng, self.g),
+                perturb_channel(&mut thread_rng, self.b),
+            ),
+            ColourSpace::Lab => {
+                let lab = self.to_lab();
+                Colour::from_lab(Lab {
+                    l: perturb(lab.l, 8.0),
+                    a: perturb(lab.a, 8.0),
+                    b: perturb(lab.b, 8.0),
+                })
+            }
+            ColourSpace::OkLab => {
+                let oklab = self.to_oklab();
+                Colour::from_oklab(OkLab {
+                    l: perturb(oklab.l, 0.05),
+                    a: perturb(oklab.a, 0.05),
+                    b: perturb(oklab.b, 0.05),
+                })
+            }
+            ColourSpace::Hsv => {
+                let hsv = self.to_hsv();
+                Colour::from_hsv(Hsv {
+                    h: perturb(hsv.h, 20.0),
+                    s: perturb(hsv.s, 0.08),
+                    v: perturb(hsv.v, 0.08),
+                })
+            }
+        };
+        Colour { a, ..perturbed }
+    }
+}
+
+/// Perturbs a channel by up to 20 in either direction
+fn perturb_channel(thread_rng: &mut impl Rng, channel: u8) -> u8 {
+    thread_rng.gen_range(channel.saturating_sub(20)..=channel.saturating_add(20))
+}
+
+/// How the difference between two colours is measured
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ColourDistance {
+    /// The L1 distance between the RGB channels
+    Rgb,
+    /// The CIE76 colour difference
+    Cie76,
+    /// The CIEDE2000 colour difference
+    Ciede2000,
+}
+
+impl ColourDistance {
+    /// The largest difference that `difference` returns
+    pub const MAX_DIFFERENCE: u64 = 4 * 256;
+
+    /// The difference between two colours, this is the distance between the colours, when
+    /// premultiplied by their alpha, plus the difference in their alpha. Perceptual distances are
+    /// scaled so that they have the same range as the RGB distance
+    pub fn difference(self, left: Colour, right: Colour) -> u64 {
+        let ((lr, lg, lb), (rr, rg, rb)) = (left.premultiplied(), right.premultiplied());
+        let alpha = (left.a as i32 - right.a as i32).unsigned_abs() as u64;
+        let colour = match self {
+            ColourDistance::Rgb => {
+                return (lr as i32 - rr as i32).unsigned_abs() as u64
+                    + (lg as i32 - rg as i32).unsigned_abs() as u64
+                    + (lb as i32 - rb as i32).unsigned_abs() as u64
+                    + alpha;
+            }
+            ColourDistance::Cie76 => Colour::new(lr, lg, lb)
+                .to_lab()
+                .delta_e_cie76(&Colour::new(rr, rg, rb).to_lab()),
+            ColourDistance::Ciede2000 => Colour::new(lr, lg, lb)
+                .to_lab()
+                .delta_e_ciede2000(&Colour::new(rr, rg, rb).to_lab()),
+        };
+        // The largest difference between two sRGB colours is scaled to the largest RGB distance
+        let largest = match self {
+            ColourDistance::Cie76 => MAX_CIE76,
+            _ => MAX_CIEDE2000,
+        };
+        (colour * 3.0 * 255.0 / largest).round().min(3.0 * 255.0) as u64 + alpha
+    }
+}
+
+/// The largest CIE76 difference between two sRGB colours, that of blue and green
+const MAX_CIE76: f64 = 258.7;
+/// The largest CIEDE2000 difference between two sRGB colours, roughly that of (0, 0, 110) and
+/// (143, 255, 0)
+const MAX_CIEDE2000: f64 = 119.5;
+
+/// The number of iterations of Lloyd's algorithm that `Colour::kmeans` performs
+const KMEANS_ITERATIONS: usize = 16;
+
+impl Colour {
+    /// Finds a palette of up to `count` colours that represents `colours`, by k-means clustering
+    /// of their channels (including alpha). The clusters are seeded by k-means++, so the palette
+    /// only has fewer than `count` colours if `colours` has fewer distinct colours
+    pub fn kmeans(colours: &[Colour], count: usize) -> Vec<Colour> {
+        assert!(!colours.is_empty(), "There must be colours to cluster");
+        let mut thread_rng = rand::thread_rng();
+
+        // Each new centre is picked with probability proportional to its squared distance from
+        // the nearest existing centre
+        let mut centres = vec![colours[thread_rng.gen_range(0..colours.len())]];
+        let mut distances: Vec<u64> = colours
+            .iter()
+            .map(|c| c.distance_squared(centres[0]))
+            .collect();
+        while centres.len() < count {
+            let total: u64 = distances.iter().sum();
+            if total == 0 {
+                break;
+            }
+            let mut pick = thread_rng.gen_range(0..total);
+            let index = distances
+                .iter()
+                .position(|&distance| {
+                    if pick < distance {
+                        true
+                    } else {
+                        pick -= distance;
+                        false
+                    }
+                })
+                .unwrap();
+            let centre = colours[index];
+            centres.push(centre);
+            for (distance, colour) in distances.iter_mut().zip(colours) {
+                *distance = (*distance).min(colour.distance_squared(centre));
+            }
+        }
+
+        // Moves every centre to the mean of the colours nearest to it, a centre without any
+        // colours stays where it is
+        for _ in 0..KMEANS_ITERATIONS {
+            let mut sums = vec![[0u64; 5]; centres.len()];
+            for colour in colours {
+                let sum = &mut sums[colour.get_nearest(&centres)];
+                for (total, channel) in sum
+                    .iter_mut()
+                    .zip([colour.r, colour.g, colour.b, colour.a, 1])
+                {
+                    *total += channel as u64;
+                }
+            }
+            let mut moved = false;
+            for (centre, [r, g, b, a, n]) in centres.iter_mut().zip(sums) {
+                if n > 0 {
+                    let mean = |total: u64| ((total + n / 2) / n) as u8;
+                    let updated = Colour::new_rgba(mean(r), mean(g), mean(b), mean(a));
+                    moved |= updated != *centre;
+                    *centre = updated;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+        centres
+    }
+
+    /// Gets the index of the colour of `palette` nearest to this colour, by the squared distance
+    /// between their channels, ties are given to the lowest index
+    pub fn get_nearest(&self, palette: &[Colour]) -> usize {
+        (0..palette.len())
+            .min_by_key(|&i| self.distance_squared(palette[i]))
+            .expect("The palette must have a colour")
+    }
+
+    /// The squared distance between the channels (including alpha) of the colours
+    fn distance_squared(&self, other: Colour) -> u64 {
+        [
+            (self.r, other.r),
+            (self.g, other.g),
+            (self.b, other.b),
+            (self.a, other.a),
+        ]
+        .iter()
+        .map(|&(l, r)| (l as i64 - r as i64).pow(2) as u64)
+        .sum()
+    }
+}
+
+impl FromStr for ColourDistance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rgb" => Ok(ColourDistance::Rgb),
+            "cie76" => Ok(ColourDistance::Cie76),
+            "ciede2000" => Ok(ColourDistance::Ciede2000),
+            _ => Err(format!("unknown colour distance `{}`", s)),
+        }
+    }
+}
+
+impl Encode for Colour {
+    fn encode(&self, out: &mut Vec<u8>) {
+        [self.r, self.g, self.b, self.a].encode(out);
+    }
+}
+
+impl Decode for Colour {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let [r, g, b, a] = Decode::decode(input)?;
+        Some(Colour { r, g, b, a })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Colours spread across the sRGB cube
+    fn sample_colours() -> Vec<Colour> {
+        let mut colours = Vec::new();
+        for r in (0..=255).step_by(51) {
+            for g in (0..=255).step_by(51) {
+                for b in (0..=255).step_by(51) {
+                    colours.push(Colour::new(r, g, b));
+                }
+            }
+        }
+        colours
+    }
+
+    #[test]
+    fn colours_are_blended_source_over() {
+        let backgrounds = [
+            Colour::new(0, 0, 255),
+            Colour::new_rgba(30, 200, 90, 100),
+            Colour::new_rgba(255, 255, 255, 1),
+        ];
+        for background in backgrounds {
+            // An opaque colour hides the background, and a transparent one shows it unchanged
+            let opaque = Colour::new(12, 34, 56);
+            assert_eq!(opaque.blend_over(background), opaque);
+            let clear = Colour::new_rgba(200, 100, 50, 0);
+            assert_eq!(clear.blend_over(background), background);
+        }
+        assert_eq!(
+            Colour::transparent().blend_over(Colour::transparent()),
+            Colour::transparent()
+        );
+
+        // Half of the red is blended with half of the blue
+        let red = Colour::new_rgba(255, 0, 0, 128);
+        assert_eq!(
+            red.blend_over(Colour::new(0, 0, 255)),
+            Colour::new_rgba(128, 0, 127, 255)
+        );
+        assert_eq!(red.blend_over(Colour::transparent()), red);
+    }
+
+    #[test]
+    fn premultiplying_scales_by_the_alpha() {
+        assert_eq!(Colour::new(255, 100, 50).premultiplied(), (255, 100, 50));
+        assert_eq!(
+            Colour::new_rgba(255, 100, 50, 128).premultiplied(),
+            (128, 50, 25)
+        );
+        assert_eq!(Colour::new_rgba(255, 100, 50, 0).premultiplied(), (0, 0, 0));
+    }
+
+    #[test]
+    fn conversions_match_references_and_round_trip() {
+        let close = |left: [f64; 3], right: [f64; 3]| {
+            left.iter().zip(right).all(|(l, r)| (l - r).abs() < 1e-2)
+        };
+        let lab = |c: Colour| {
+            let lab = c.to_lab();
+            [lab.l, lab.a, lab.b]
+        };
+        let oklab = |c: Colour| {
+            let oklab = c.to_oklab();
+            [oklab.l, oklab.a, oklab.b]
+        };
+        let hsv = |c: Colour| {
+            let hsv = c.to_hsv();
+            [hsv.h, hsv.s, hsv.v]
+        };
+        assert!(close(lab(Colour::new(255, 255, 255)), [100.0, 0.0, 0.0]));
+        assert!(close(lab(Colour::new(255, 0, 0)), [53.24, 80.09, 67.20]));
+        assert!(close(oklab(Colour::new(255, 255, 255)), [1.0, 0.0, 0.0]));
+        assert!(close(oklab(Colour::new(255, 0, 0)), [0.628, 0.225, 0.126]));
+        assert!(close(hsv(Colour::new(0, 255, 0)), [120.0, 1.0, 1.0]));
+        assert!(close(
+            hsv(Colour::new(0, 0, 127)),
+            [240.0, 1.0, 127.0 / 255.0]
+        ));
+
+        for colour in sample_colours() {
+            assert_eq!(Colour::from_lab(colour.to_lab()), colour);
+            assert_eq!(Colour::from_oklab(colour.to_oklab()), colour);
+            assert_eq!(Colour::from_hsv(colour.to_hsv()), colour);
+        }
+    }
+
+    #[test]
+    fn ciede2000_matches_sharma_reference_pairs() {
+        // The test data of Sharma, Wu and Dalal (2005), as (L1, a1, b1, L2, a2, b2, ΔE00)
+        let pairs = [
+            [50.0, 2.6772, -79.7751, 50.0, 0.0, -82.7485, 2.0425],
+            [50.0, 3.1571, -77.2803, 50.0, 0.0, -82.7485, 2.8615],
+            [50.0, 2.8361, -74.0200, 50.0, 0.0, -82.7485, 3.4412],
+            [50.0, -1.3802, -84.2814, 50.0, 0.0, -82.7485, 1.0000],
+            [50.0, -1.1848, -84.8006, 50.0, 0.0, -82.7485, 1.0000],
+            [50.0, -0.9009, -85.5211, 50.0, 0.0, -82.7485, 1.0000],
+            [50.0, 0.0, 0.0, 50.0, -1.0, 2.0, 2.3669],
+            [50.0, -1.0, 2.0, 50.0, 0.0, 0.0, 2.3669],
+            [50.0, 2.49, -0.001, 50.0, -2.49, 0.0009, 7.1792],
+            [50.0, 2.49, -0.001, 50.0, -2.49, 0.0010, 7.1792],
+            [50.0, 2.49, -0.001, 50.0, -2.49, 0.0011, 7.2195],
+            [50.0, 2.49, -0.001, 50.0, -2.49, 0.0012, 7.2195],
+            [50.0, -0.001, 2.49, 50.0, 0.0009, -2.49, 4.8045],
+            [50.0, -0.001, 2.49, 50.0, 0.0010, -2.49, 4.8045],
+            [50.0, -0.001, 2.49, 50.0, 0.0011, -2.49, 4.7461],
+            [50.0, 2.5, 0.0, 50.0, 0.0, -2.5, 4.3065],
+            [50.0, 2.5, 0.0, 73.0, 25.0, -18.0, 27.1492],
+            [50.0, 2.5, 0.0, 61.0, -5.0, 29.0, 22.8977],
+            [50.0, 2.5, 0.0, 56.0, -27.0, -3.0, 31.9030],
+            [50.0, 2.5, 0.0, 58.0, 24.0, 15.0, 19.4535],
+            [50.0, 2.5, 0.0, 50.0, 3.1736, 0.5854, 1.0000],
+            [50.0, 2.5, 0.0, 50.0, 3.2972, 0.0, 1.0000],
+            [50.0, 2.5, 0.0, 50.0, 1.8634, 0.5757, 1.0000],
+            [50.0, 2.5, 0.0, 50.0, 3.2592, 0.3350, 1.0000],
+            [
+                60.2574, -34.0099, 36.2677, 60.4626, -34.1751, 39.4387, 1.2644,
+            ],
+            [
+                63.0109, -31.0961, -5.8663, 62.8187, -29.7946, -4.0864, 1.2630,
+            ],
+            [61.2901, 3.7196, -5.3901, 61.4292, 2.2480, -4.9620, 1.8731],
+            [35.0831, -44.1164, 3.7933, 35.0232, -40.0716, 1.5901, 1.8645],
+            [
+                22.7233, 20.0904, -46.6940, 23.0331, 14.9730, -42.5619, 2.0373,
+            ],
+            [36.4612, 47.8580, 18.3852, 36.2715, 50.5065, 21.2231, 1.4146],
+            [90.8027, -2.0831, 1.4410, 91.1528, -1.6435, 0.0447, 1.4441],
+            [90.9257, -0.5406, -0.9208, 88.6381, -0.8985, -0.7239, 1.5381],
+            [6.7747, -0.2908, -2.4247, 5.8714, -0.0985, -2.2286, 0.6377],
+            [2.0776, 0.0795, -1.1350, 0.9033, -0.0636, -0.5514, 0.9082],
+        ];
+        for [l1, a1, b1, l2, a2, b2, expected] in pairs {
+            let first = Lab {
+                l: l1,
+                a: a1,
+                b: b1,
+            };
+            let second = Lab {
+                l: l2,
+                a: a2,
+                b: b2,
+            };
+            assert!((first.delta_e_ciede2000(&second) - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn distances_have_the_same_range() {
+        let (blue, green) = (Colour::new(0, 0, 255), Colour::new(0, 255, 0));
+        let (black, white) = (Colour::new(0, 0, 0), Colour::new(255, 255, 255));
+        assert_eq!(ColourDistance::Rgb.difference(black, white), 765);
+        assert_eq!(ColourDistance::Cie76.difference(blue, green), 765);
+        assert_eq!(
+            ColourDistance::Ciede2000.difference(Colour::new(0, 0, 110), Colour::new(143, 255, 0)),
+            765
+        );
+        for distance in [ColourDistance::Cie76, ColourDistance::Ciede2000] {
+            for colour in sample_colours() {
+                assert!(distance.difference(colour, black) <= 765);
+                assert_eq!(distance.difference(colour, colour), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn kmeans_of_distinct_colours_returns_them() {
+        let distinct = [
+            Colour::new(0, 0, 0),
+            Colour::new(255, 255, 255),
+            Colour::new(200, 30, 30),
+            Colour::new(201, 30, 30),
+            Colour::new_rgba(10, 120, 240, 128),
+        ];
+        // Every colour appears a different number of times, so that the clusters are uneven
+        let colours: Vec<Colour> = distinct
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &colour)| vec![colour; i * 7 + 1])
+            .collect();
+        let key = |c: &Colour| (c.r, c.g, c.b, c.a);
+        let mut expected = distinct.to_vec();
+        expected.sort_by_key(key);
+        for count in [distinct.len(), distinct.len() + 3] {
+            let mut palette = Colour::kmeans(&colours, count);
+            palette.sort_by_key(key);
+            assert_eq!(palette, expected);
+        }
     }
 }
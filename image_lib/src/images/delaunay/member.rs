@@ -0,0 +1,26 @@
+use crate::images::delaunay::DelaunayImage;
+use crate::images::{BreedMetadata, Breedable, ImageMember};
+
+/// A member which evolves a Delaunay image
+pub type DelaunayMember<'a, U> = ImageMember<'a, U, DelaunayImage>;
+
+/// The images are bred by a spatial crossover, which is the only operator recorded
+impl Breedable for DelaunayImage {
+    fn breed_traced<F>(
+        left: &Self,
+        right: &Self,
+        metadata: &BreedMetadata,
+        mut on_operator: F,
+    ) -> Self
+    where
+        F: FnMut(&'static str),
+    {
+        on_operator("spatial_crossover");
+        DelaunayImage::breed(
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_mutation_space(),
+        )
+    }
+}
@@ -0,0 +1,142 @@
+/// An image whose structure is a free set of points, which is triangulated with a Delaunay
+/// triangulation whenever it is rasterized. The root of the image is top left
+use crate::colour::Colour;
+use crate::images::{ColourMode, Triangulated};
+use crate::point::Point;
+use rand::Rng;
+mod member;
+mod mutation;
+mod triangulation;
+mod wire;
+pub use member::DelaunayMember;
+
+/// The corners of the image, these are always the first points of an image and can't be moved or
+/// deleted, so that the triangulation always covers the whole image
+const CORNERS: [Point; 4] = [
+    Point { x: 0, y: 0 },
+    Point { x: u16::MAX, y: 0 },
+    Point {
+        x: u16::MAX,
+        y: u16::MAX,
+    },
+    Point { x: 0, y: u16::MAX },
+];
+
+#[derive(Clone, PartialEq)]
+pub struct DelaunayImage {
+    /// The position of every point, the first four points are the corners of the image, no two
+    /// points have the same position
+    points: Vec<Point>,
+    /// The colour of every point
+    colours: Vec<Colour>,
+    /// With ColourMode::Flat each triangle has the average colour of its points
+    colour_mode: ColourMode,
+    /// The real width and height of the image in pixels, points are always within
+    /// `0..u16::MAX` and are scaled to these dimensions when rasterizing to pixels
+    dimensions: (u32, u32),
+}
+
+impl DelaunayImage {
+    /// Creates an image which is `width` x `height` pixels, with `count` points placed at random
+    /// as well as the corners, every point has a random colour
+    pub fn new_random(count: usize, width: u32, height: u32) -> DelaunayImage {
+        assert!(width > 0 && height > 0, "The image must have an area");
+        let mut image = DelaunayImage {
+            points: CORNERS.to_vec(),
+            colours: CORNERS.map(|_| random_colour()).to_vec(),
+            colour_mode: ColourMode::Flat,
+            dimensions: (width, height),
+        };
+        let mut thread_rng = rand::thread_rng();
+        while image.points.len() < CORNERS.len() + count {
+            let point = Point::new(thread_rng.gen(), thread_rng.gen());
+            image.insert_point(point, random_colour());
+        }
+        image
+    }
+
+    pub fn get_points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn get_colours(&self) -> &[Colour] {
+        &self.colours
+    }
+
+    pub fn get_colour_mode(&self) -> ColourMode {
+        self.colour_mode
+    }
+
+    /// Delaunay images have no palette, so they can't be coloured with ColourMode::Palette
+    pub fn set_colour_mode(&mut self, colour_mode: ColourMode) {
+        assert!(
+            colour_mode != ColourMode::Palette,
+            "Delaunay images have no palette"
+        );
+        self.colour_mode = colour_mode;
+    }
+
+    /// Gets the Delaunay triangulation of the points, each triangle is the indices of its points
+    pub fn get_triangulation(&self) -> Vec<[usize; 3]> {
+        triangulation::triangulate(&self.points)
+    }
+}
+
+impl Triangulated for DelaunayImage {
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn get_shaded_triangles<F>(&self, mut on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]),
+    {
+        for triangle in self.get_triangulation() {
+            let colours = triangle.map(|i| self.colours[i]);
+            let colours = match self.colour_mode {
+                ColourMode::Flat => {
+                    let average = |channel: fn(&Colour) -> u8| {
+                        ((colours.iter().map(|c| channel(c) as u32).sum::<u32>() + 1) / 3) as u8
+                    };
+                    let colour = Colour::new_rgba(
+                        average(|c| c.r),
+                        average(|c| c.g),
+                        average(|c| c.b),
+                        average(|c| c.a),
+                    );
+                    [colour; 3]
+                }
+                ColourMode::Gouraud => colours,
+                ColourMode::Palette => unreachable!("Delaunay images have no palette"),
+            };
+            on_triangle([
+                (self.points[triangle[0]], colours[0]),
+                (self.points[triangle[1]], colours[1]),
+                (self.points[triangle[2]], colours[2]),
+            ]);
+        }
+    }
+}
+
+fn random_colour() -> Colour {
+    Colour::new(rand::random(), rand::random(), rand::random())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_pixel_is_rasterized_exactly_once() {
+        for _ in 0..20 {
+            let image = DelaunayImage::new_random(60, 640, 480);
+            for size in [(64, 64), (97, 61)] {
+                let mut covered = vec![0u8; size.0 as usize * size.1 as usize];
+                image.rasterize_pixels(size, (0, 0), |p, _| {
+                    covered[p.y as usize * size.0 as usize + p.x as usize] += 1;
+                });
+                assert!(covered.iter().all(|&n| n == 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use super::{DelaunayImage, CORNERS};
+use crate::colour::{Colour, ColourSpace};
+use crate::point::Point;
+use rand::{random, Rng};
+
+impl DelaunayImage {
+    /// Inserts a point, returns false if there is already a point at the position
+    pub fn insert_point(&mut self, point: Point, colour: Colour) -> bool {
+        if self.points.contains(&point) {
+            return false;
+        }
+        self.points.push(point);
+        self.colours.push(colour);
+        true
+    }
+
+    /// Deletes the point at `index`, the corners can't be deleted
+    pub fn delete_point(&mut self, index: usize) {
+        assert!(index >= CORNERS.len(), "The corners can't be deleted");
+        self.points.swap_remove(index);
+        self.colours.swap_remove(index);
+    }
+
+    /// Moves the point at `index` by up to `radius` in each axis, the point isn't moved if it is
+    /// a corner or if there is already a point at the new position
+    pub fn move_point(&mut self, index: usize, radius: u16) {
+        if index < CORNERS.len() {
+            return;
+        }
+        let mut thread_rng = rand::thread_rng();
+        let mut shift = |value: u16| {
+            thread_rng.gen_range(value.saturating_sub(radius)..=value.saturating_add(radius))
+        };
+        let point = self.points[index];
+        let moved = Point::new(shift(point.x), shift(point.y));
+        if !self.points.contains(&moved) {
+            self.points[index] = moved;
+        }
+    }
+
+    /// Gets the index of a random point that can be moved or deleted, i.e. any point that isn't a
+    /// corner, if there is one
+    pub fn get_random_free_point(&self) -> Option<usize> {
+        if self.points.len() > CORNERS.len() {
+            Some(rand::thread_rng().gen_range(CORNERS.len()..self.points.len()))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the index of the point closest to `point`
+    fn nearest_point(&self, point: Point) -> usize {
+        let distance =
+            |p: &Point| (p.x as i64 - point.x as i64).pow(2) + (p.y as i64 - point.y as i64).pow(2);
+        (0..self.points.len())
+            .min_by_key(|i| distance(&self.points[*i]))
+            .unwrap()
+    }
+}
+
+/// A collection of functions to aid genetic mutation and breeding
+impl DelaunayImage {
+    /// Breeds the images with a spatial crossover, the child has the points of the left parent
+    /// on one side of a random line and the points of the right parent on the other.
+    /// Each point of the child then has a `mutation_rate` chance of being moved and of its colour
+    /// being mutated in `space`, and a quarter of that chance of being deleted, a point is
+    /// inserted with the same chance, so that the number of points is stable
+    pub fn breed(
+        left: &DelaunayImage,
+        right: &DelaunayImage,
+        mutation_rate: f32,
+        space: ColourSpace,
+    ) -> DelaunayImage {
+        let mut thread_rng = rand::thread_rng();
+
+        // Cuts the images along a random vertical or horizontal line
+        let vertical: bool = random();
+        let cut: u16 = thread_rng.gen();
+        let side = |p: &Point| if vertical { p.x < cut } else { p.y < cut };
+        let mut image = DelaunayImage {
+            points: CORNERS.to_vec(),
+            colours: (0..CORNERS.len())
+                .map(|i| {
+                    if random() {
+                        left.colours[i]
+                    } else {
+                        right.colours[i]
+                    }
+                })
+                .collect(),
+            ..left.clone()
+        };
+        for (parent, from_left) in [(left, true), (right, false)] {
+            for i in CORNERS.len()..parent.points.len() {
+                if side(&parent.points[i]) == from_left {
+                    image.insert_point(parent.points[i], parent.colours[i]);
+                }
+            }
+        }
+
+        // Mutates the points, the points are visited in reverse so that deleting a point doesn't
+        // skip the point that replaces it
+        let mut insertions = 0;
+        for i in (CORNERS.len()..image.points.len()).rev() {
+            if random::<f32>() < mutation_rate {
+                image.move_point(i, 4096);
+            }
+            if random::<f32>() < mutation_rate {
+                image.colours[i] = image.colours[i].perturb(space);
+            }
+            if random::<f32>() < mutation_rate / 4.0 {
+                image.delete_point(i);
+            }
+            if random::<f32>() < mutation_rate / 4.0 {
+                insertions += 1;
+            }
+        }
+        // An inserted point takes the colour of the point nearest to it
+        for _ in 0..insertions {
+            let point = Point::new(thread_rng.gen(), thread_rng.gen());
+            let colour = image.colours[image.nearest_point(point)];
+            image.insert_point(point, colour);
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every point has a colour, the corners come first and no two points are at the same
+    /// position
+    fn assert_consistent(image: &DelaunayImage) {
+        assert_eq!(image.points.len(), image.colours.len());
+        assert!(image.points.starts_with(&CORNERS));
+        for (i, point) in image.points.iter().enumerate() {
+            assert!(!image.points[..i].contains(point));
+        }
+    }
+
+    #[test]
+    fn points_are_inserted_deleted_and_moved() {
+        let mut image = DelaunayImage::new_random(20, 64, 64);
+        let red = Colour::new(255, 0, 0);
+
+        // A point can't be inserted on top of another
+        assert!(image.insert_point(Point::new(100, 200), red));
+        assert!(!image.insert_point(Point::new(100, 200), red));
+        assert!(!image.insert_point(CORNERS[1], red));
+        assert_eq!(image.points.len(), CORNERS.len() + 21);
+        assert_eq!(image.colours[CORNERS.len() + 20], red);
+
+        // Deleting a point swaps the last point into its place along with its colour
+        let last = (image.points[24], image.colours[24]);
+        image.delete_point(10);
+        assert_eq!((image.points[10], image.colours[10]), last);
+        assert_consistent(&image);
+
+        for _ in 0..1000 {
+            let index = rand::thread_rng().gen_range(0..image.points.len());
+            let point = image.points[index];
+            image.move_point(index, 100);
+            let moved = image.points[index];
+            if index < CORNERS.len() {
+                assert_eq!(moved, point);
+            }
+            assert!(moved.x.abs_diff(point.x) <= 100 && moved.y.abs_diff(point.y) <= 100);
+        }
+        assert_consistent(&image);
+    }
+
+    #[test]
+    #[should_panic]
+    fn corners_cant_be_deleted() {
+        DelaunayImage::new_random(5, 64, 64).delete_point(3);
+    }
+
+    #[test]
+    fn bred_images_are_consistent() {
+        let left = DelaunayImage::new_random(40, 64, 64);
+        let right = DelaunayImage::new_random(40, 64, 64);
+        for _ in 0..100 {
+            let child = DelaunayImage::breed(&left, &right, 0.5, ColourSpace::Lab);
+            assert_consistent(&child);
+        }
+    }
+}
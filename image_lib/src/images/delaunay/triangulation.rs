@@ -0,0 +1,128 @@
+use super::CORNERS;
+use crate::point::Point;
+
+/// Twice the signed area of the triangle (a, b, c), positive for the order used by the images'
+/// triangles
+fn orientation(a: Point, b: Point, c: Point) -> i64 {
+    (b.x as i64 - a.x as i64) * (c.y as i64 - a.y as i64)
+        - (b.y as i64 - a.y as i64) * (c.x as i64 - a.x as i64)
+}
+
+/// Whether `d` is strictly inside the circumcircle of the positively oriented triangle (a, b, c),
+/// this is exact as the coordinates are at most 16 bits
+fn in_circumcircle(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let row = |p: Point| {
+        let (x, y) = (p.x as i128 - d.x as i128, p.y as i128 - d.y as i128);
+        (x, y, x * x + y * y)
+    };
+    let (a, b, c) = (row(a), row(b), row(c));
+    a.0 * (b.1 * c.2 - b.2 * c.1) - a.1 * (b.0 * c.2 - b.2 * c.0) + a.2 * (b.0 * c.1 - b.1 * c.0)
+        > 0
+}
+
+/// Triangulates the points with the Bowyer-Watson algorithm, the first points must be the corners
+/// of the image, which start as two triangles covering the image, every other point is inside
+/// them and so no super triangle is needed. Every triangle is positively oriented, and points
+/// which duplicate an earlier point are ignored
+pub(super) fn triangulate(points: &[Point]) -> Vec<[usize; 3]> {
+    debug_assert!(points[..CORNERS.len()] == CORNERS);
+    let mut triangles = vec![[0, 1, 2], [0, 2, 3]];
+
+    for (i, &point) in points.iter().enumerate().skip(CORNERS.len()) {
+        if points[..i].contains(&point) {
+            continue;
+        }
+
+        // The cavity is every triangle whose circumcircle contains the point, its boundary is
+        // the edges which are only used by one of the triangles in it
+        let (cavity, kept): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles
+            .into_iter()
+            .partition(|t| in_circumcircle(points[t[0]], points[t[1]], points[t[2]], point));
+        let edges: Vec<(usize, usize)> = cavity
+            .iter()
+            .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+            .collect();
+        triangles = kept;
+
+        // Connects the point to the boundary of the cavity, as the triangles are all oriented
+        // the same way a shared edge appears once in each direction. The point may lie on an
+        // edge of the image, in which case that edge would give a degenerate triangle
+        for &(a, b) in &edges {
+            if !edges.contains(&(b, a)) && orientation(points[a], points[b], point) > 0 {
+                triangles.push([a, b, i]);
+            }
+        }
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// The corners followed by random points, some of which lie on the image's edges
+    fn random_points(count: usize) -> Vec<Point> {
+        let mut thread_rng = rand::thread_rng();
+        let mut points = CORNERS.to_vec();
+        for i in 0..count {
+            let (x, y) = (thread_rng.gen(), thread_rng.gen());
+            points.push(match i % 8 {
+                0 => Point::new(x, 0),
+                1 => Point::new(u16::MAX, y),
+                2 => Point::new(x, u16::MAX),
+                3 => Point::new(0, y),
+                _ => Point::new(x, y),
+            });
+        }
+        points
+    }
+
+    #[test]
+    fn triangulations_are_delaunay_and_cover_the_image() {
+        for _ in 0..20 {
+            let points = random_points(60);
+            let triangles = triangulate(&points);
+            let mut area = 0;
+            for &[a, b, c] in &triangles {
+                let (a, b, c) = (points[a], points[b], points[c]);
+                assert!(orientation(a, b, c) > 0);
+                area += orientation(a, b, c);
+                // No point is inside the circumcircle of any triangle
+                assert!(points.iter().all(|&d| !in_circumcircle(a, b, c, d)));
+            }
+            // The triangles are positively oriented and inside the image, so if their area is
+            // the image's then they cover it without overlapping
+            assert_eq!(area, 2 * u16::MAX as i64 * u16::MAX as i64);
+        }
+    }
+
+    #[test]
+    fn points_on_the_edges_are_triangulated() {
+        let mut points = CORNERS.to_vec();
+        points.extend([
+            Point::new(30000, 0),
+            Point::new(u16::MAX, 20000),
+            Point::new(10000, u16::MAX),
+            Point::new(0, 40000),
+        ]);
+        let triangles = triangulate(&points);
+        // Each edge point splits one of the corner triangles' edges, which gives a triangle more
+        // for each point, and none of the triangles are degenerate
+        assert_eq!(triangles.len(), 6);
+        for i in CORNERS.len()..points.len() {
+            assert!(triangles.iter().any(|t| t.contains(&i)));
+        }
+        for &[a, b, c] in &triangles {
+            assert!(orientation(points[a], points[b], points[c]) > 0);
+        }
+    }
+
+    #[test]
+    fn duplicate_points_are_ignored() {
+        let mut points = random_points(30);
+        let triangles = triangulate(&points);
+        points.extend([points[10], points[20], CORNERS[2]]);
+        assert_eq!(triangulate(&points), triangles);
+    }
+}
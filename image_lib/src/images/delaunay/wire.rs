@@ -0,0 +1,64 @@
+use super::{DelaunayImage, CORNERS};
+use crate::colour::Colour;
+use crate::images::{decode_dimensions, ColourMode};
+use crate::point::Point;
+use genetic_algorithm_lib::workers::{Decode, Encode};
+
+/// A Delaunay image is encoded as its points, then the colour of every point, then its colour
+/// mode (0 for flat, 1 for Gouraud) and finally its real dimensions
+impl Encode for DelaunayImage {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.points.encode(out);
+        self.colours.encode(out);
+        (self.colour_mode as u8).encode(out);
+        self.dimensions.encode(out);
+    }
+}
+
+impl Decode for DelaunayImage {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let points = Vec::<Point>::decode(input)?;
+        let colours = Vec::<Colour>::decode(input)?;
+        // Every point needs a colour, and the corners must come first
+        if points.len() != colours.len() || !points.starts_with(&CORNERS) {
+            return None;
+        }
+        let colour_mode = match u8::decode(input)? {
+            0 => ColourMode::Flat,
+            1 => ColourMode::Gouraud,
+            _ => return None,
+        };
+        let dimensions = decode_dimensions(input)?;
+        Some(DelaunayImage {
+            points,
+            colours,
+            colour_mode,
+            dimensions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_round_trip() {
+        for colour_mode in [ColourMode::Flat, ColourMode::Gouraud] {
+            let mut image = DelaunayImage::new_random(50, 640, 480);
+            image.set_colour_mode(colour_mode);
+            let mut genome = Vec::new();
+            image.encode(&mut genome);
+            assert!(DelaunayImage::decode(&mut genome.as_slice()).unwrap() == image);
+        }
+    }
+
+    #[test]
+    fn images_without_their_corners_are_rejected() {
+        let mut image = DelaunayImage::new_random(10, 640, 480);
+        image.points.swap(0, 5);
+        let mut genome = Vec::new();
+        image.encode(&mut genome);
+        assert!(DelaunayImage::decode(&mut genome.as_slice()).is_none());
+    }
+}
@@ -0,0 +1,229 @@
+use super::mutation::{breed_appearance, breed_structure};
+use super::{Colour, DynGridImage, GridImage};
+use crate::images::{image_fitness, BreedMetadata, FitnessMetadata, Triangulated};
+use genetic_algorithm_lib::{Collaboration, Member};
+
+/// A grid image whose structure and appearance can be bred separately, so that they can be
+/// evolved by cooperating species, either a GridImage or a DynGridImage
+pub trait CoevolvedGrid: Triangulated + Clone {
+    /// Breeds the structure of the images, the child's appearance is that of one of the parents
+    fn breed_structure(left: &Self, right: &Self, metadata: &BreedMetadata) -> Self;
+
+    /// Replaces the appearance of the image with a crossover of the parents' appearance
+    fn breed_appearance(&mut self, left: &Self, right: &Self, metadata: &BreedMetadata);
+
+    /// Replaces the appearance of this image with the appearance of `other`, which must have
+    /// the same grid size
+    fn copy_appearance(&mut self, other: &Self);
+}
+
+impl<const W: usize, const H: usize> CoevolvedGrid for GridImage<W, H> {
+    fn breed_structure(left: &Self, right: &Self, metadata: &BreedMetadata) -> Self {
+        GridImage::breed_structure(
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_structure_mutation(),
+            &mut |_| {},
+        )
+    }
+
+    fn breed_appearance(&mut self, left: &Self, right: &Self, metadata: &BreedMetadata) {
+        GridImage::breed_appearance(
+            self,
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_mutation_space(),
+            &mut |_| {},
+        );
+    }
+
+    fn copy_appearance(&mut self, other: &Self) {
+        GridImage::copy_appearance(self, other);
+    }
+}
+
+/// The species must have the same grid size
+impl CoevolvedGrid for DynGridImage {
+    fn breed_structure(left: &Self, right: &Self, metadata: &BreedMetadata) -> Self {
+        breed_structure(
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_structure_mutation(),
+            &mut |_| {},
+        )
+    }
+
+    fn breed_appearance(&mut self, left: &Self, right: &Self, metadata: &BreedMetadata) {
+        breed_appearance(
+            self,
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_mutation_space(),
+            &mut |_| {},
+        );
+    }
+
+    fn copy_appearance(&mut self, other: &Self) {
+        DynGridImage::copy_appearance(self, other);
+    }
+}
+
+/// The geometry species for cooperative coevolution of a grid image, only the structure (the
+/// vertex positions) of the image is evolved, its appearance is taken from a ColourSpecies
+pub struct GeometrySpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    /// Only the structure of this image is used
+    image: I,
+    /// A function which returns the colour of the target image at the given point
+    get_target_pixel: &'a U,
+}
+
+/// The colour species for cooperative coevolution of a grid image, only the appearance (the
+/// colours) of the image is evolved, its structure is taken from a GeometrySpecies
+pub struct ColourSpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    /// Only the appearance of this image is used
+    image: I,
+    /// A function which returns the colour of the target image at the given point
+    get_target_pixel: &'a U,
+}
+
+impl<'a, U, I> GeometrySpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: CoevolvedGrid,
+{
+    pub fn new(image: I, get_target_pixel: &'a U) -> Self {
+        GeometrySpecies {
+            image,
+            get_target_pixel,
+        }
+    }
+
+    /// Creates the image with this member's structure and the appearance of `colours`
+    pub fn combine(&self, colours: &ColourSpecies<'a, U, I>) -> I {
+        let mut image = self.image.clone();
+        image.copy_appearance(&colours.image);
+        image
+    }
+}
+
+impl<'a, U, I> ColourSpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: CoevolvedGrid,
+{
+    pub fn new(image: I, get_target_pixel: &'a U) -> Self {
+        ColourSpecies {
+            image,
+            get_target_pixel,
+        }
+    }
+}
+
+impl<'a, U, I> Clone for GeometrySpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: CoevolvedGrid,
+{
+    fn clone(&self) -> Self {
+        GeometrySpecies::new(self.image.clone(), self.get_target_pixel)
+    }
+}
+
+impl<'a, U, I> Clone for ColourSpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: CoevolvedGrid,
+{
+    fn clone(&self) -> Self {
+        ColourSpecies::new(self.image.clone(), self.get_target_pixel)
+    }
+}
+
+impl<'a, U, I> Member for GeometrySpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: CoevolvedGrid,
+{
+    type FitnessMetadata = Collaboration<ColourSpecies<'a, U, I>, FitnessMetadata>;
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, metadata: &Self::FitnessMetadata) -> u64 {
+        let image = self.combine(&metadata.collaborator);
+        image_fitness(&image, self.get_target_pixel, &metadata.metadata)
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        let image = I::breed_structure(&left.image, &right.image, metadata);
+        GeometrySpecies::new(image, left.get_target_pixel)
+    }
+}
+
+impl<'a, U, I> Member for ColourSpecies<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: CoevolvedGrid,
+{
+    type FitnessMetadata = Collaboration<GeometrySpecies<'a, U, I>, FitnessMetadata>;
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, metadata: &Self::FitnessMetadata) -> u64 {
+        let image = metadata.collaborator.combine(self);
+        image_fitness(&image, self.get_target_pixel, &metadata.metadata)
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        let mut image = left.image.clone();
+        image.breed_appearance(&left.image, &right.image, metadata);
+        ColourSpecies::new(image, left.get_target_pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::{AxisResolution, Resolution};
+    use genetic_algorithm_lib::Coevolution;
+
+    #[test]
+    fn coevolution_improves_the_combined_fitness() {
+        // A target split into two colours, so that both the structure and the colours matter
+        let get_target_pixel = |x: u16, _y: u16| {
+            if x < 20 {
+                Colour::new(200, 40, 40)
+            } else {
+                Colour::new(30, 90, 220)
+            }
+        };
+        let metadata = FitnessMetadata::new(
+            (0, 0),
+            Resolution(AxisResolution::Blocks64, AxisResolution::Blocks64),
+        );
+        let breed_metadata = BreedMetadata::new(0.2);
+        let new_image = || DynGridImage::new_uniform_with_dimensions(4, 4, 64, 64);
+        let mut coevolution: Coevolution<_, _, _, 10, 10> = Coevolution::new(
+            [0; 10].map(|_| GeometrySpecies::new(new_image(), &get_target_pixel)),
+            [0; 10].map(|_| ColourSpecies::new(new_image(), &get_target_pixel)),
+        );
+
+        let mut fitnesses = Vec::new();
+        for _ in 0..40 {
+            let (next, fitness) = coevolution.run(&metadata, &breed_metadata, &breed_metadata);
+            coevolution = next;
+            fitnesses.push(fitness);
+        }
+        let (geometry, colours) = coevolution.best();
+        let combined = image_fitness(&geometry.combine(colours), &get_target_pixel, &metadata);
+        assert!(fitnesses[39] > fitnesses[0]);
+        assert_eq!(combined, fitnesses[39]);
+    }
+}
@@ -0,0 +1,56 @@
+use super::DynGridImage;
+use crate::colour::{Colour, ColourSpace};
+use crate::images::grid::MeshDefect;
+use crate::images::{BreedMetadata, Breedable, ImageMember, Meshed, Refinable, StructureMutation};
+
+/// A member which evolves a grid image of any grid size
+pub type DynGridMember<'a, U> = ImageMember<'a, U, DynGridImage>;
+
+impl<'a, U> DynGridMember<'a, U>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    /// Converts the member to a grid of `width` x `height` nodes keeping its appearance, see
+    /// `DynGridImage::resample`. The resampled member is a child of this member
+    pub fn resample(&self, width: usize, height: usize) -> Self {
+        self.derive(self.get_image().resample(width, height), "resample")
+    }
+}
+
+/// The parents must have the same grid size
+impl Breedable for DynGridImage {
+    fn breed_traced<F>(left: &Self, right: &Self, metadata: &BreedMetadata, on_operator: F) -> Self
+    where
+        F: FnMut(&'static str),
+    {
+        DynGridImage::breed_traced(
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_structure_mutation(),
+            metadata.get_mutation_space(),
+            on_operator,
+        )
+    }
+}
+
+impl Meshed for DynGridImage {
+    fn validate(&self) -> Vec<MeshDefect> {
+        DynGridImage::validate(self)
+    }
+
+    fn repair(&mut self) {
+        DynGridImage::repair(self);
+    }
+}
+
+/// See `GridImage`'s implementation
+impl Refinable for DynGridImage {
+    fn mutate_random_vertex(&mut self, structure: StructureMutation) {
+        DynGridImage::mutate_random_vertex(self, structure);
+    }
+
+    fn mutate_random_colour(&mut self, space: ColourSpace) {
+        DynGridImage::mutate_random_colour(self, space);
+    }
+}
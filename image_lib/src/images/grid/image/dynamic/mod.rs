@@ -0,0 +1,495 @@
+use super::initialise::{initialise_colours, ColourInitialisation};
+use super::mutation;
+use super::placement::{place_vertices, VertexPlacement};
+use super::rasters::{get_shaded_triangles, rasterize_box};
+use super::resample::resample;
+use super::validation::{repair, validate, MeshDefect};
+use super::{
+    assert_palette_size, count_invalid_triangles, reindex_palette, Colour, GridImage, GridStorage,
+    GridVertex, Point, DEFAULT_PALETTE_SIZE,
+};
+use crate::colour::ColourSpace;
+use crate::images::{ColourMode, StructureMutation, Triangulated};
+mod member;
+mod wire;
+pub use member::DynGridMember;
+
+/// A grid based image whose grid size is chosen at run time, it has `width` nodes in the
+/// horizontal axis and `height` nodes in the vertical axis. It supports the same operations as
+/// GridImage, but its vertices and colours are stored on the heap, so large grids can't overflow
+/// the stack
+#[derive(Clone, PartialEq)]
+pub struct DynGridImage {
+    /// The number of nodes in the horizontal axis
+    width: usize,
+    /// The number of nodes in the vertical axis
+    height: usize,
+    /// The positions of every vertex in the graph, in row order
+    vertex_positions: Vec<Point>,
+    /// The colours of the two triangles of the cell of every vertex, in row order, as with
+    /// GridImage the right and bottom edges' colours are stored even though they aren't used
+    colours: Vec<(Colour, Colour)>,
+    /// The colour of every vertex, in row order, used when shading with ColourMode::Gouraud
+    vertex_colours: Vec<Colour>,
+    /// Whether the cell of every vertex has its diagonal flipped, in row order
+    flipped: Vec<bool>,
+    /// The index into `palette` of the colours of the two triangles of the cell of every vertex,
+    /// in row order, used when colouring with ColourMode::Palette
+    palette_indices: Vec<(u8, u8)>,
+    /// The colours that every triangle chooses from with ColourMode::Palette, there are between
+    /// 1 and 256 colours
+    palette: Vec<Colour>,
+    /// Whether the triangles are coloured by `colours`, by `vertex_colours` or by `palette`
+    colour_mode: ColourMode,
+    /// The real width and height of the image in pixels
+    dimensions: (u32, u32),
+}
+
+impl DynGridImage {
+    /// Creates a square, 65536 x 65536, image with `width` x `height` nodes
+    pub fn new_uniform(width: usize, height: usize) -> DynGridImage {
+        DynGridImage::new_uniform_with_dimensions(
+            width,
+            height,
+            u16::MAX as u32 + 1,
+            u16::MAX as u32 + 1,
+        )
+    }
+
+    /// Creates an image with `width` x `height` nodes, which is `pixel_width` x `pixel_height`
+    /// pixels, e.g. the dimensions of the target. The grid must have at least 3 nodes in each
+    /// axis, so that it has inner vertices to mutate, and at most 65536, so that every node has
+    /// its own position in the domain
+    pub fn new_uniform_with_dimensions(
+        width: usize,
+        height: usize,
+        pixel_width: u32,
+        pixel_height: u32,
+    ) -> DynGridImage {
+        assert!(
+            width >= 3 && height >= 3,
+            "The grid must have at least one inner vertex"
+        );
+        assert!(
+            width <= 65536 && height <= 65536,
+            "The grid can't have more nodes than the domain has positions"
+        );
+        assert!(
+            pixel_width > 0 && pixel_height > 0,
+            "The image must have an area"
+        );
+        let separation = (
+            u16::MAX / (width - 1) as u16,
+            u16::MAX / (height - 1) as u16,
+        );
+        let vertex_positions = (0..width * height)
+            .map(|i| Point {
+                x: (i % width) as u16 * separation.0,
+                y: (i / width) as u16 * separation.1,
+            })
+            .collect();
+        let colours = (0..width * height)
+            .map(|_| (random_colour(), random_colour()))
+            .collect();
+        let vertex_colours = (0..width * height).map(|_| random_colour()).collect();
+        let palette_indices = (0..width * height)
+            .map(|_| {
+                (
+                    rand::random::<u8>() % DEFAULT_PALETTE_SIZE,
+                    rand::random::<u8>() % DEFAULT_PALETTE_SIZE,
+                )
+            })
+            .collect();
+        let palette = (0..DEFAULT_PALETTE_SIZE).map(|_| random_colour()).collect();
+        DynGridImage {
+            width,
+            height,
+            vertex_positions,
+            colours,
+            vertex_colours,
+            flipped: vec![false; width * height],
+            palette_indices,
+            palette,
+            colour_mode: ColourMode::Flat,
+            dimensions: (pixel_width, pixel_height),
+        }
+    }
+
+    /// Gets the number of nodes in each axis
+    pub fn get_grid_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Gets the positions of every vertex, in row order
+    pub fn get_vertex_positions(&self) -> &[Point] {
+        &self.vertex_positions
+    }
+
+    /// Gets the colours of every cell, in row order
+    pub fn get_colours(&self) -> &[(Colour, Colour)] {
+        &self.colours
+    }
+
+    /// Gets the colours of every vertex, in row order
+    pub fn get_vertex_colours(&self) -> &[Colour] {
+        &self.vertex_colours
+    }
+
+    /// Gets whether the cell of every vertex has its diagonal flipped, in row order
+    pub fn get_flipped(&self) -> &[bool] {
+        &self.flipped
+    }
+
+    /// Gets the index into the palette of the colours of every cell's triangles, in row order
+    pub fn get_palette_indices(&self) -> &[(u8, u8)] {
+        &self.palette_indices
+    }
+
+    pub fn get_palette(&self) -> &[Colour] {
+        &self.palette
+    }
+
+    /// See `GridImage::set_palette`
+    pub fn set_palette(&mut self, palette: Vec<Colour>) {
+        assert_palette_size(&palette);
+        self.palette = palette;
+        reindex_palette(self);
+    }
+
+    pub fn get_colour_mode(&self) -> ColourMode {
+        self.colour_mode
+    }
+
+    /// Changes how the image's triangles are coloured, the colours of the other mode are kept,
+    /// so switching back restores the previous appearance
+    pub fn set_colour_mode(&mut self, colour_mode: ColourMode) {
+        self.colour_mode = colour_mode;
+    }
+
+    /// Replaces the appearance (the colours, the palette and the colour mode) of this image with
+    /// the appearance of `other`, which must have the same grid size, leaving the structure unchanged
+    pub fn copy_appearance(&mut self, other: &DynGridImage) {
+        assert!(
+            self.get_grid_size() == other.get_grid_size(),
+            "The images must have the same grid size"
+        );
+        self.colours.clone_from(&other.colours);
+        self.vertex_colours.clone_from(&other.vertex_colours);
+        self.palette_indices.clone_from(&other.palette_indices);
+        self.palette.clone_from(&other.palette);
+        self.colour_mode = other.colour_mode;
+    }
+
+    pub fn get_random_inner_vertex(&self) -> GridVertex {
+        GridVertex::random_inner(self.get_grid_size())
+    }
+
+    /// Gets a random vertex, including those on the edges of the grid
+    pub fn get_random_vertex(&self) -> GridVertex {
+        GridVertex::random(self.get_grid_size())
+    }
+
+    /// Gets a random vertex on an edge of the grid, which isn't a corner
+    pub fn get_random_boundary_vertex(&self) -> GridVertex {
+        GridVertex::random_boundary(self.get_grid_size())
+    }
+
+    /// Gets the top left vertex of a random cell, i.e. any vertex not on the right or bottom edge
+    pub fn get_random_cell(&self) -> GridVertex {
+        GridVertex::random_cell(self.get_grid_size())
+    }
+
+    /// Counts the triangles that are inverted or have zero area
+    pub fn count_invalid_triangles(&self) -> usize {
+        count_invalid_triangles(self)
+    }
+
+    /// See `GridImage::validate`
+    pub fn validate(&self) -> Vec<MeshDefect> {
+        validate(self)
+    }
+
+    /// See `GridImage::repair`
+    pub fn repair(&mut self) {
+        repair(self);
+    }
+
+    /// Converts the image to a grid with `width` x `height` nodes, keeping its appearance, see
+    /// `GridImage::resample`. The new grid must have at least 3 nodes in each axis
+    pub fn resample(&self, width: usize, height: usize) -> DynGridImage {
+        assert!(
+            width >= 3 && height >= 3,
+            "The grid must have at least one inner vertex"
+        );
+        assert!(
+            width <= 65536 && height <= 65536,
+            "The grid can't have more nodes than the domain has positions"
+        );
+        let mut image = DynGridImage {
+            width,
+            height,
+            vertex_positions: vec![Point { x: 0, y: 0 }; width * height],
+            colours: vec![(Colour::transparent(), Colour::transparent()); width * height],
+            vertex_colours: vec![Colour::transparent(); width * height],
+            flipped: vec![false; width * height],
+            palette_indices: vec![(0, 0); width * height],
+            palette: self.palette.clone(),
+            colour_mode: self.colour_mode,
+            dimensions: self.dimensions,
+        };
+        resample(self, &mut image);
+        image
+    }
+
+    /// See `GridImage::initialise_colours`
+    pub fn initialise_colours<U>(
+        &mut self,
+        get_target_pixel: &U,
+        initialisation: ColourInitialisation,
+    ) where
+        U: Fn(u16, u16) -> Colour,
+    {
+        initialise_colours(self, get_target_pixel, initialisation);
+    }
+
+    /// See `GridImage::place_vertices`
+    pub fn place_vertices<U>(&mut self, get_target_pixel: &U, placement: VertexPlacement)
+    where
+        U: Fn(u16, u16) -> Colour,
+    {
+        place_vertices(self, get_target_pixel, placement);
+    }
+
+    /// See `GridImage::rasterize_box`
+    pub fn rasterize_box<F>(&self, resolution: (u8, u8), on_point: F)
+    where
+        F: FnMut(Point, Colour),
+    {
+        rasterize_box(self, resolution, on_point);
+    }
+
+    /// Gets the index of a vertex in the row ordered vectors
+    fn index(&self, vert: &GridVertex) -> usize {
+        vert.vertical * self.width + vert.horizontal
+    }
+}
+
+/// See GridImage for the documentation of each mutation
+impl DynGridImage {
+    pub fn mutate_structure(&mut self, vert: &GridVertex, radius: Option<u32>) {
+        mutation::mutate_structure(self, vert, radius);
+    }
+
+    pub fn mutate_structure_gaussian(&mut self, vert: &GridVertex, sigma: f32) {
+        mutation::mutate_structure_gaussian(self, vert, sigma);
+    }
+
+    pub fn slide_boundary_vertex(&mut self, vert: &GridVertex, structure: StructureMutation) {
+        mutation::slide_boundary_vertex(self, vert, structure);
+    }
+
+    pub fn mutate_random_vertex(&mut self, structure: StructureMutation) {
+        mutation::mutate_random_vertex(self, structure);
+    }
+
+    pub fn flip_diagonal(&mut self, vert: &GridVertex) -> bool {
+        mutation::flip_diagonal(self, vert)
+    }
+
+    pub fn mutate_triangle_colour(&mut self, vert: &GridVertex, upper: bool, space: ColourSpace) {
+        mutation::mutate_triangle_colour(self, vert, upper, space);
+    }
+
+    pub fn mutate_vertex_colour(&mut self, vert: &GridVertex, space: ColourSpace) {
+        mutation::mutate_vertex_colour(self, vert, space);
+    }
+
+    pub fn mutate_palette_index(&mut self, vert: &GridVertex, upper: bool) {
+        mutation::mutate_palette_index(self, vert, upper);
+    }
+
+    pub fn mutate_palette_colour(&mut self, index: usize, space: ColourSpace) {
+        mutation::mutate_palette_colour(self, index, space);
+    }
+
+    pub fn mutate_random_colour(&mut self, space: ColourSpace) {
+        mutation::mutate_random_colour(self, space);
+    }
+
+    pub fn mutate_colours(&mut self, mutation_rate: f32, space: ColourSpace) {
+        mutation::mutate_colours(self, mutation_rate, space);
+    }
+
+    /// The parents must have the same grid size
+    pub fn breed(left: &DynGridImage, right: &DynGridImage, mutation_rate: f32) -> DynGridImage {
+        DynGridImage::breed_traced(
+            left,
+            right,
+            mutation_rate,
+            StructureMutation::Uniform(None),
+            ColourSpace::Rgb,
+            |_| {},
+        )
+    }
+
+    /// The parents must have the same grid size
+    pub fn breed_traced<F>(
+        left: &DynGridImage,
+        right: &DynGridImage,
+        mutation_rate: f32,
+        structure: StructureMutation,
+        space: ColourSpace,
+        on_operator: F,
+    ) -> DynGridImage
+    where
+        F: FnMut(&'static str),
+    {
+        assert!(
+            left.get_grid_size() == right.get_grid_size(),
+            "The parents must have the same grid size"
+        );
+        mutation::breed_traced(left, right, mutation_rate, structure, space, on_operator)
+    }
+}
+
+impl GridStorage for DynGridImage {
+    fn get_grid_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn get_vert_position(&self, vert: &GridVertex) -> Point {
+        self.vertex_positions[self.index(vert)]
+    }
+
+    fn set_vert_position(&mut self, vert: &GridVertex, position: Point) {
+        let index = self.index(vert);
+        self.vertex_positions[index] = position;
+    }
+
+    fn get_vert_colours(&self, vert: &GridVertex) -> (Colour, Colour) {
+        self.colours[self.index(vert)]
+    }
+
+    fn set_vert_colours(&mut self, vert: &GridVertex, colours: (Colour, Colour)) {
+        let index = self.index(vert);
+        self.colours[index] = colours;
+    }
+
+    fn get_vert_colour(&self, vert: &GridVertex) -> Colour {
+        self.vertex_colours[self.index(vert)]
+    }
+
+    fn set_vert_colour(&mut self, vert: &GridVertex, colour: Colour) {
+        let index = self.index(vert);
+        self.vertex_colours[index] = colour;
+    }
+
+    fn get_vert_flipped(&self, vert: &GridVertex) -> bool {
+        self.flipped[self.index(vert)]
+    }
+
+    fn set_vert_flipped(&mut self, vert: &GridVertex, flipped: bool) {
+        let index = self.index(vert);
+        self.flipped[index] = flipped;
+    }
+
+    fn get_vert_palette_indices(&self, vert: &GridVertex) -> (u8, u8) {
+        self.palette_indices[self.index(vert)]
+    }
+
+    fn set_vert_palette_indices(&mut self, vert: &GridVertex, indices: (u8, u8)) {
+        let index = self.index(vert);
+        self.palette_indices[index] = indices;
+    }
+
+    fn get_palette(&self) -> &[Colour] {
+        &self.palette
+    }
+
+    fn set_palette_colour(&mut self, index: usize, colour: Colour) {
+        self.palette[index] = colour;
+    }
+
+    fn get_colour_mode(&self) -> ColourMode {
+        self.colour_mode
+    }
+}
+
+impl Triangulated for DynGridImage {
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn get_shaded_triangles<F>(&self, on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]),
+    {
+        get_shaded_triangles(self, on_triangle);
+    }
+}
+
+impl<const W: usize, const H: usize> From<&GridImage<W, H>> for DynGridImage {
+    fn from(image: &GridImage<W, H>) -> DynGridImage {
+        DynGridImage {
+            width: W,
+            height: H,
+            vertex_positions: image.vertex_positions.iter().flatten().copied().collect(),
+            colours: image.colours.iter().flatten().copied().collect(),
+            vertex_colours: image.vertex_colours.iter().flatten().copied().collect(),
+            flipped: image.flipped.iter().flatten().copied().collect(),
+            palette_indices: image.palette_indices.iter().flatten().copied().collect(),
+            palette: image.palette.clone(),
+            colour_mode: image.colour_mode,
+            dimensions: image.dimensions,
+        }
+    }
+}
+
+fn random_colour() -> Colour {
+    Colour::new(rand::random(), rand::random(), rand::random())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::{AxisResolution, FitnessMetadata, ImageMember, Resolution};
+    use genetic_algorithm_lib::Member;
+
+    fn pixels<T: Triangulated>(image: &T) -> Vec<(Point, Colour)> {
+        let mut pixels = Vec::new();
+        image.rasterize_pixels((97, 61), (0, 0), |p, c| pixels.push((p, c)));
+        pixels
+    }
+
+    #[test]
+    fn dynamic_images_match_grid_images() {
+        let uniform: GridImage<8, 6> = GridImage::new_uniform_with_dimensions(640, 480);
+        let dynamic = DynGridImage::new_uniform_with_dimensions(8, 6, 640, 480);
+        assert!(uniform
+            .get_vertex_positions()
+            .iter()
+            .flatten()
+            .eq(dynamic.get_vertex_positions()));
+
+        let target = |x: u16, y: u16| Colour::new((x / 3) as u8, (y / 2) as u8, 100);
+        let metadata = FitnessMetadata::new(
+            (0, 0),
+            Resolution(AxisResolution::Blocks64, AxisResolution::Blocks64),
+        );
+        let mut image = uniform;
+        for _ in 0..1000 {
+            image.mutate_structure(&image.get_random_inner_vertex(), None);
+            image.flip_diagonal(&image.get_random_cell());
+            image.mutate_random_colour(ColourSpace::Rgb);
+        }
+        for colour_mode in [ColourMode::Flat, ColourMode::Gouraud, ColourMode::Palette] {
+            image.set_colour_mode(colour_mode);
+            let dynamic = DynGridImage::from(&image);
+            assert_eq!(pixels(&dynamic), pixels(&image));
+            assert_eq!(
+                ImageMember::new(dynamic, &target).fitness(&metadata),
+                ImageMember::new(image.clone(), &target).fitness(&metadata)
+            );
+        }
+    }
+}
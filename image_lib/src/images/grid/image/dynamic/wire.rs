@@ -0,0 +1,79 @@
+use super::{Colour, DynGridImage, Point};
+use crate::images::{decode_dimensions, ColourMode};
+use genetic_algorithm_lib::workers::{Decode, Encode};
+
+/// A DynGridImage is encoded in the same way as a GridImage of the same grid size, so either can
+/// decode the other's genomes
+impl Encode for DynGridImage {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.width as u32, self.height as u32).encode(out);
+        for value in &self.vertex_positions {
+            value.encode(out);
+        }
+        for value in &self.colours {
+            value.encode(out);
+        }
+        for value in &self.vertex_colours {
+            value.encode(out);
+        }
+        for value in &self.flipped {
+            value.encode(out);
+        }
+        for value in &self.palette_indices {
+            value.encode(out);
+        }
+        self.palette.encode(out);
+        (self.colour_mode as u8).encode(out);
+        self.dimensions.encode(out);
+    }
+}
+
+impl Decode for DynGridImage {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let (width, height) = <(u32, u32)>::decode(input)?;
+        let (width, height) = (width as usize, height as usize);
+        if width < 3 || height < 3 {
+            return None;
+        }
+        let count = width.checked_mul(height)?;
+        let vertex_positions = decode_values::<Point>(input, count)?;
+        let colours = decode_values::<(Colour, Colour)>(input, count)?;
+        let vertex_colours = decode_values::<Colour>(input, count)?;
+        let flipped = decode_values::<bool>(input, count)?;
+        let palette_indices = decode_values::<(u8, u8)>(input, count)?;
+        let palette = Vec::<Colour>::decode(input)?;
+        // Every index must be within the palette
+        let in_palette = |index: u8| (index as usize) < palette.len();
+        if palette.len() > 256
+            || !palette_indices
+                .iter()
+                .all(|&(upper, lower)| in_palette(upper) && in_palette(lower))
+        {
+            return None;
+        }
+        let colour_mode = match u8::decode(input)? {
+            0 => ColourMode::Flat,
+            1 => ColourMode::Gouraud,
+            2 => ColourMode::Palette,
+            _ => return None,
+        };
+        let dimensions = decode_dimensions(input)?;
+        Some(DynGridImage {
+            width,
+            height,
+            vertex_positions,
+            colours,
+            vertex_colours,
+            flipped,
+            palette_indices,
+            palette,
+            colour_mode,
+            dimensions,
+        })
+    }
+}
+
+/// Decodes `count` values which aren't length prefixed
+fn decode_values<T: Decode>(input: &mut &[u8], count: usize) -> Option<Vec<T>> {
+    (0..count).map(|_| T::decode(input)).collect()
+}
@@ -0,0 +1,292 @@
+use super::{
+    cell_triangles, incident_cells, reindex_palette, Colour, GridImage, GridStorage, GridVertex,
+    Point,
+};
+use crate::images::Triangulated;
+use rand::Rng;
+use std::str::FromStr;
+
+/// How the colours of an image are initialised from a target
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColourInitialisation {
+    /// Each triangle has the mean colour of the target pixels it covers
+    Mean,
+    /// Each triangle has the mean colour, with each channel (except alpha) moved by up to the
+    /// given amount at random, so that images initialised from the same target differ
+    Jittered(u8),
+    /// Each triangle has the mean colour of the target blurred by a box of the given radius in
+    /// pixels, which softens the edges between triangles
+    Blurred(u32),
+}
+
+impl FromStr for ColourInitialisation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "mean" {
+            return Ok(ColourInitialisation::Mean);
+        }
+        if let Some(Ok(amount)) = s.strip_prefix("jitter:").map(u8::from_str) {
+            return Ok(ColourInitialisation::Jittered(amount));
+        }
+        match s.strip_prefix("blur:").map(u32::from_str) {
+            Some(Ok(radius)) => Ok(ColourInitialisation::Blurred(radius)),
+            _ => Err(format!("unknown colour initialisation `{}`", s)),
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> GridImage<W, H> {
+    /// Creates an image which is `width` x `height` pixels, with the colours of the target,
+    /// see `GridImage::initialise_colours`
+    pub fn new_uniform_from_target<U>(
+        width: u32,
+        height: u32,
+        get_target_pixel: &U,
+        initialisation: ColourInitialisation,
+    ) -> GridImage<W, H>
+    where
+        U: Fn(u16, u16) -> Colour,
+    {
+        let mut image = GridImage::new_uniform_with_dimensions(width, height);
+        image.initialise_colours(get_target_pixel, initialisation);
+        image
+    }
+
+    /// Colours every triangle with the mean colour of the target pixels that it covers, and
+    /// every vertex with the mean colour of the triangles that include it. Every triangle is then
+    /// given the palette colour nearest to its colour. `get_target_pixel` returns the colour of
+    /// the target at the given pixel, the target is assumed to have the same dimensions as the
+    /// image. A triangle which covers no pixels takes the colour of the pixel at its centroid
+    pub fn initialise_colours<U>(
+        &mut self,
+        get_target_pixel: &U,
+        initialisation: ColourInitialisation,
+    ) where
+        U: Fn(u16, u16) -> Colour,
+    {
+        initialise_colours(self, get_target_pixel, initialisation);
+    }
+}
+
+/// See `GridImage::initialise_colours`
+pub(super) fn initialise_colours<G, U>(
+    grid: &mut G,
+    get_target_pixel: &U,
+    initialisation: ColourInitialisation,
+) where
+    G: GridStorage + Triangulated,
+    U: Fn(u16, u16) -> Colour,
+{
+    let (width, height) = grid.get_dimensions();
+    let blurred = match initialisation {
+        ColourInitialisation::Blurred(radius) => {
+            Some(box_blur(get_target_pixel, (width, height), radius))
+        }
+        _ => None,
+    };
+    let get_pixel = |x: u16, y: u16| match &blurred {
+        Some(blurred) => blurred[y as usize * width as usize + x as usize],
+        None => get_target_pixel(x, y),
+    };
+    let jitter = |colour: Colour| match initialisation {
+        ColourInitialisation::Jittered(amount) => jitter(colour, amount),
+        _ => colour,
+    };
+
+    // Sums the target pixels that each triangle covers
+    let (columns, rows) = grid.get_grid_size();
+    let mut sums = vec![[0u64; 5]; 2 * columns * rows];
+    Indexed(&*grid).rasterize_pixels((width, height), (0, 0), |p, c| {
+        let sum = &mut sums[u32::from_le_bytes([c.r, c.g, c.b, c.a]) as usize];
+        let colour = get_pixel(p.x, p.y);
+        for (total, channel) in sum
+            .iter_mut()
+            .zip([colour.r, colour.g, colour.b, colour.a, 1])
+        {
+            *total += channel as u64;
+        }
+    });
+
+    // Colours each triangle with its mean
+    let mut means = vec![Colour::transparent(); 2 * columns * rows];
+    for vertical in 0..rows - 1 {
+        for horizontal in 0..columns - 1 {
+            let vert = GridVertex::new(horizontal, vertical);
+            for (upper, triangle) in [true, false].into_iter().zip(cell_triangles(grid, &vert)) {
+                let index = triangle_index(columns, &vert, upper);
+                means[index] = match sums[index] {
+                    [r, g, b, a, n] if n > 0 => {
+                        let mean = |total: u64| ((total + n / 2) / n) as u8;
+                        Colour::new_rgba(mean(r), mean(g), mean(b), mean(a))
+                    }
+                    _ => {
+                        let centroid = |axis: fn(&Point) -> u16, dimension: u32| {
+                            let total: u64 = triangle
+                                .iter()
+                                .map(|corner| axis(&grid.get_vert_position(corner)) as u64)
+                                .sum();
+                            (total * dimension as u64 / (3 * 65536)).min(dimension as u64 - 1)
+                                as u16
+                        };
+                        get_pixel(centroid(|p| p.x, width), centroid(|p| p.y, height))
+                    }
+                };
+            }
+            let colours = (
+                jitter(means[triangle_index(columns, &vert, true)]),
+                jitter(means[triangle_index(columns, &vert, false)]),
+            );
+            grid.set_vert_colours(&vert, colours);
+        }
+    }
+
+    // Colours each vertex with the mean of the triangles that include it
+    for vertical in 0..rows {
+        for horizontal in 0..columns {
+            let vert = GridVertex::new(horizontal, vertical);
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+            for cell in incident_cells(grid, &vert) {
+                for (upper, triangle) in [true, false].into_iter().zip(cell_triangles(grid, &cell))
+                {
+                    if triangle.contains(&vert) {
+                        let colour = means[triangle_index(columns, &cell, upper)];
+                        for (total, channel) in
+                            sum.iter_mut().zip([colour.r, colour.g, colour.b, colour.a])
+                        {
+                            *total += channel as u32;
+                        }
+                        count += 1;
+                    }
+                }
+            }
+            let mean = |total: u32| ((total + count / 2) / count) as u8;
+            let colour = Colour::new_rgba(mean(sum[0]), mean(sum[1]), mean(sum[2]), mean(sum[3]));
+            grid.set_vert_colour(&vert, jitter(colour));
+        }
+    }
+    reindex_palette(grid);
+}
+
+/// The triangles of a grid, each coloured by its index (as the bytes of a u32), so that
+/// rasterizing it finds the triangle that covers each pixel
+struct Indexed<'a, G>(&'a G);
+
+impl<'a, G: GridStorage + Triangulated> Triangulated for Indexed<'a, G> {
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.0.get_dimensions()
+    }
+
+    fn get_shaded_triangles<F>(&self, mut on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]),
+    {
+        let (columns, rows) = self.0.get_grid_size();
+        for vertical in 0..rows - 1 {
+            for horizontal in 0..columns - 1 {
+                let vert = GridVertex::new(horizontal, vertical);
+                let triangles = cell_triangles(self.0, &vert);
+                for (upper, triangle) in [true, false].into_iter().zip(triangles) {
+                    let [r, g, b, a] = (triangle_index(columns, &vert, upper) as u32).to_le_bytes();
+                    let colour = Colour::new_rgba(r, g, b, a);
+                    on_triangle(triangle.map(|corner| (self.0.get_vert_position(&corner), colour)));
+                }
+            }
+        }
+    }
+}
+
+/// Gets the index of the upper or lower triangle of the cell whose top left vertex is `vert`, in
+/// a grid with `columns` vertices in each row
+fn triangle_index(columns: usize, vert: &GridVertex, upper: bool) -> usize {
+    2 * (vert.vertical * columns + vert.horizontal) + !upper as usize
+}
+
+/// Moves each channel of the colour (except alpha) by up to `amount` at random
+fn jitter(colour: Colour, amount: u8) -> Colour {
+    let mut thread_rng = rand::thread_rng();
+    let mut shift = |channel: u8| {
+        thread_rng.gen_range(channel.saturating_sub(amount)..=channel.saturating_add(amount))
+    };
+    Colour::new_rgba(shift(colour.r), shift(colour.g), shift(colour.b), colour.a)
+}
+
+/// Blurs the target by replacing every pixel with the mean of the pixels within `radius` of it in
+/// each axis, using a summed area table so that any radius takes the same time
+pub(super) fn box_blur<U>(get_target_pixel: &U, dimensions: (u32, u32), radius: u32) -> Vec<Colour>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    let (width, height) = (dimensions.0 as usize, dimensions.1 as usize);
+    // The sum of every pixel above and left of each position, with a row and column of zeros
+    let mut table = vec![[0u64; 4]; (width + 1) * (height + 1)];
+    for y in 0..height {
+        for x in 0..width {
+            let colour = get_target_pixel(x as u16, y as u16);
+            let channels = [colour.r, colour.g, colour.b, colour.a];
+            for channel in 0..4 {
+                table[(y + 1) * (width + 1) + x + 1][channel] = channels[channel] as u64
+                    + table[y * (width + 1) + x + 1][channel]
+                    + table[(y + 1) * (width + 1) + x][channel]
+                    - table[y * (width + 1) + x][channel];
+            }
+        }
+    }
+
+    let radius = radius as usize;
+    let mut blurred = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (left, right) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let (top, bottom) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+            let n = ((right - left) * (bottom - top)) as u64;
+            let mean = |channel: usize| {
+                let total = table[bottom * (width + 1) + right][channel]
+                    + table[top * (width + 1) + left][channel]
+                    - table[top * (width + 1) + right][channel]
+                    - table[bottom * (width + 1) + left][channel];
+                ((total + n / 2) / n) as u8
+            };
+            blurred.push(Colour::new_rgba(mean(0), mean(1), mean(2), mean(3)));
+        }
+    }
+    blurred
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::grid::DynGridImage;
+
+    #[test]
+    fn a_flat_target_colours_every_triangle_and_vertex() {
+        let colour = Colour::new_rgba(40, 120, 200, 180);
+        let target = |_, _| colour;
+        // In a 4x3 image most triangles of an 8x6 grid cover no pixels, and take the colour of
+        // the pixel at their centroid
+        for (width, height) in [(640, 480), (4, 3)] {
+            for initialisation in [ColourInitialisation::Mean, ColourInitialisation::Blurred(3)] {
+                let image: GridImage<8, 6> =
+                    GridImage::new_uniform_from_target(width, height, &target, initialisation);
+                for row in &image.get_colours()[..5] {
+                    assert!(row[..7].iter().all(|&pair| pair == (colour, colour)));
+                }
+                assert!(image
+                    .get_vertex_colours()
+                    .iter()
+                    .flatten()
+                    .all(|&c| c == colour));
+
+                let mut dynamic = DynGridImage::new_uniform_with_dimensions(8, 6, width, height);
+                dynamic.initialise_colours(&target, initialisation);
+                for (i, &pair) in dynamic.get_colours().iter().enumerate() {
+                    if i % 8 < 7 && i / 8 < 5 {
+                        assert_eq!(pair, (colour, colour));
+                    }
+                }
+                assert!(dynamic.get_vertex_colours().iter().all(|&c| c == colour));
+            }
+        }
+    }
+}
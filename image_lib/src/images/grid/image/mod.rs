@@ -3,15 +3,30 @@ use super::Colour;
 use super::Point;
 use super::Polygon;
 use super::Tri;
+use crate::images::{ColourMode, Triangulated};
 use rand::Rng;
 use std::cmp::{max, min};
 use std::iter::StepBy;
 use std::ops::Range;
+mod coevolution;
+mod dynamic;
+mod initialise;
 mod member;
 mod mutation;
+mod placement;
 mod rasters;
-pub use member::{BreedMetadata, FitnessMetadata, GAImageMember};
-pub use rasters::{AxisResolution, Resolution};
+mod resample;
+mod validation;
+mod wire;
+pub use coevolution::{CoevolvedGrid, ColourSpecies, GeometrySpecies};
+pub use dynamic::{DynGridImage, DynGridMember};
+pub use initialise::ColourInitialisation;
+pub use member::GAImageMember;
+pub use placement::VertexPlacement;
+pub use validation::MeshDefect;
+
+/// The number of colours in the palette of a new image
+const DEFAULT_PALETTE_SIZE: u8 = 16;
 
 /// W - 1 and H - 1 are upper bounds for the horizontal and vertical values
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -56,23 +71,8 @@ impl GridVertex {
     }
 }
 
-/// The set of edges between neighbours of a vertex
-enum GridNeighbourEdgeSet {
-    Centre([(GridVertex, GridVertex); 6]),
-
-    TopLeftCorner([(GridVertex, GridVertex); 2]),
-    TopRightCorner([(GridVertex, GridVertex); 1]),
-    BottomLeftCorner([(GridVertex, GridVertex); 1]),
-    BottomRightCorner([(GridVertex, GridVertex); 2]),
-
-    LeftEdge([(GridVertex, GridVertex); 3]),
-    TopEdge([(GridVertex, GridVertex); 3]),
-    RightEdge([(GridVertex, GridVertex); 3]),
-    BottomEdge([(GridVertex, GridVertex); 3]),
-}
-
 /// A grid based image, it has W nodes in the horizontal axis and H nodes in the vertical axis
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct GridImage<const W: usize, const H: usize> {
     /// The positions of every vertex in the graph, this is a lookup table for the point function
     /// indexed by horizontal then vertical
@@ -80,10 +80,35 @@ pub struct GridImage<const W: usize, const H: usize> {
     /// Stores the colours of each vertex that is capable of having a colour
     /// we store colours for the right and bottom edges even though they cannot be used
     colours: [[(Colour, Colour); W]; H],
+    /// The colour of every vertex, these are only used when shading with ColourMode::Gouraud
+    vertex_colours: [[Colour; W]; H],
+    /// Whether the cell of each vertex is split along its diagonal from top right to bottom left,
+    /// rather than from top left to bottom right, the right and bottom edges' values are unused
+    flipped: [[bool; W]; H],
+    /// The index into `palette` of the colours of each cell's triangles, these are only used when
+    /// colouring with ColourMode::Palette, as with `colours` the right and bottom edges' values
+    /// are unused
+    palette_indices: [[(u8, u8); W]; H],
+    /// The colours that every triangle chooses from with ColourMode::Palette, there are between
+    /// 1 and 256 colours
+    palette: Vec<Colour>,
+    /// Whether the triangles are coloured by `colours`, by `vertex_colours` or by `palette`
+    colour_mode: ColourMode,
+    /// The real width and height of the image in pixels, vertex positions are always within
+    /// `0..u16::MAX` and are scaled to these dimensions when rasterizing to pixels
+    dimensions: (u32, u32),
 }
 
 impl<const W: usize, const H: usize> GridImage<W, H> {
+    /// Creates a square, 65536 x 65536, image
     pub fn new_uniform() -> GridImage<W, H> {
+        GridImage::new_uniform_with_dimensions(u16::MAX as u32 + 1, u16::MAX as u32 + 1)
+    }
+
+    /// Creates an image which is `width` x `height` pixels, e.g. the dimensions of the target
+    pub fn new_uniform_with_dimensions(width: u32, height: u32) -> GridImage<W, H> {
+        assert!(width > 0 && height > 0, "The image must have an area");
+        let dimensions = (width, height);
         let width = u16::MAX;
         let height = u16::MAX;
 
@@ -101,22 +126,33 @@ impl<const W: usize, const H: usize> GridImage<W, H> {
         let colours = [0; H].map(|_| {
             [0; W].map(|_| {
                 (
-                    Colour {
-                        r: rand::random(),
-                        g: rand::random(),
-                        b: rand::random(),
-                    },
-                    Colour {
-                        r: rand::random(),
-                        g: rand::random(),
-                        b: rand::random(),
-                    },
+                    Colour::new(rand::random(), rand::random(), rand::random()),
+                    Colour::new(rand::random(), rand::random(), rand::random()),
                 )
             })
         });
+        let vertex_colours = [0; H]
+            .map(|_| [0; W].map(|_| Colour::new(rand::random(), rand::random(), rand::random())));
+        let palette_indices = [0; H].map(|_| {
+            [0; W].map(|_| {
+                (
+                    rand::random::<u8>() % DEFAULT_PALETTE_SIZE,
+                    rand::random::<u8>() % DEFAULT_PALETTE_SIZE,
+                )
+            })
+        });
+        let palette = (0..DEFAULT_PALETTE_SIZE)
+            .map(|_| Colour::new(rand::random(), rand::random(), rand::random()))
+            .collect();
         let mut image = GridImage {
             vertex_positions,
             colours,
+            vertex_colours,
+            flipped: [[false; W]; H],
+            palette_indices,
+            palette,
+            colour_mode: ColourMode::Flat,
+            dimensions,
         };
         // Applies a random number of structure mutations
         // for _ in 0..rand::thread_rng().gen_range(0..=2048) {
@@ -133,11 +169,82 @@ impl<const W: usize, const H: usize> GridImage<W, H> {
         &self.colours
     }
 
+    pub fn get_vertex_colours(&self) -> &[[Colour; W]; H] {
+        &self.vertex_colours
+    }
+
+    /// Gets whether the cell of each vertex has its diagonal flipped
+    pub fn get_flipped(&self) -> &[[bool; W]; H] {
+        &self.flipped
+    }
+
+    /// Gets the index into the palette of the colours of every cell's triangles
+    pub fn get_palette_indices(&self) -> &[[(u8, u8); W]; H] {
+        &self.palette_indices
+    }
+
+    pub fn get_palette(&self) -> &[Colour] {
+        &self.palette
+    }
+
+    /// Replaces the palette, which must have between 1 and 256 colours, e.g. one found by
+    /// `Colour::kmeans` from the target. Every triangle is given the palette colour nearest to
+    /// its flat colour, so switching from ColourMode::Flat to ColourMode::Palette keeps the
+    /// image's appearance as far as the palette allows
+    pub fn set_palette(&mut self, palette: Vec<Colour>) {
+        assert_palette_size(&palette);
+        self.palette = palette;
+        reindex_palette(self);
+    }
+
+    pub fn get_colour_mode(&self) -> ColourMode {
+        self.colour_mode
+    }
+
+    /// Changes how the image's triangles are coloured, the colours of the other mode are kept,
+    /// so switching back restores the previous appearance
+    pub fn set_colour_mode(&mut self, colour_mode: ColourMode) {
+        self.colour_mode = colour_mode;
+    }
+
+    /// Replaces the appearance (the colours, the palette and the colour mode) of this image with
+    /// the appearance of `other`, leaving the structure (including the cells' diagonals) unchanged
+    pub fn copy_appearance(&mut self, other: &GridImage<W, H>) {
+        self.colours = other.colours;
+        self.vertex_colours = other.vertex_colours;
+        self.palette_indices = other.palette_indices;
+        self.palette.clone_from(&other.palette);
+        self.colour_mode = other.colour_mode;
+    }
+
     pub fn get_random_inner_vertex(&self) -> GridVertex {
-        GridVertex {
-            horizontal: (rand::random::<usize>() % (W - 2)) + 1,
-            vertical: (rand::random::<usize>() % (H - 2)) + 1,
-        }
+        GridVertex::random_inner((W, H))
+    }
+
+    /// Gets a random vertex, including those on the edges of the grid
+    pub fn get_random_vertex(&self) -> GridVertex {
+        GridVertex::random((W, H))
+    }
+
+    /// Gets a random vertex on an edge of the grid, which isn't a corner
+    pub fn get_random_boundary_vertex(&self) -> GridVertex {
+        GridVertex::random_boundary((W, H))
+    }
+
+    /// Gets the top left vertex of a random cell, i.e. any vertex not on the right or bottom edge
+    pub fn get_random_cell(&self) -> GridVertex {
+        GridVertex::random_cell((W, H))
+    }
+
+    /// Counts the triangles that are inverted or have zero area
+    pub fn count_invalid_triangles(&self) -> usize {
+        count_invalid_triangles(self)
+    }
+}
+
+impl<const W: usize, const H: usize> GridStorage for GridImage<W, H> {
+    fn get_grid_size(&self) -> (usize, usize) {
+        (W, H)
     }
 
     /// Looks up a vertex's position (applies the point function)
@@ -145,127 +252,263 @@ impl<const W: usize, const H: usize> GridImage<W, H> {
         self.vertex_positions[vert.vertical][vert.horizontal]
     }
 
+    fn set_vert_position(&mut self, vert: &GridVertex, position: Point) {
+        self.vertex_positions[vert.vertical][vert.horizontal] = position;
+    }
+
     fn get_vert_colours(&self, vert: &GridVertex) -> (Colour, Colour) {
         self.colours[vert.vertical][vert.horizontal]
     }
 
-    /// Gets the neighbours of a vertex in the grid, there are at most 8 neighbours and so this
-    /// returns at most 8 neighbours
-    fn neighbour_edge_set(&self, vert: &GridVertex) -> GridNeighbourEdgeSet {
-        match vert {
-            // -- If the vertex is a corner vertex, it has 3 neighbours
-            // Top Left
-            GridVertex {
-                horizontal: 0,
-                vertical: 0,
-            } => GridNeighbourEdgeSet::TopLeftCorner([
-                (vert.right(), vert.down_right()),
-                (vert.down_right(), vert.down()),
-            ]),
-            // Bottom Left
-            GridVertex {
-                horizontal: h,
-                vertical: v,
-            } if *h == 0 && *v == H - 1 => {
-                GridNeighbourEdgeSet::BottomLeftCorner([(vert.up(), vert.right())])
-            }
-            // Top Right
-            GridVertex {
-                horizontal: h,
-                vertical: v,
-            } if *h == W - 1 && *v == 0 => {
-                GridNeighbourEdgeSet::TopRightCorner([(vert.down(), vert.left())])
-            }
-            // Bottom Right
-            GridVertex {
-                horizontal: h,
-                vertical: v,
-            } if *h == W - 1 && *v == H - 1 => GridNeighbourEdgeSet::BottomRightCorner([
-                (vert.left(), vert.up_left()),
-                (vert.up_left(), vert.up()),
-            ]),
-            // If the vertex is an edge vertex, it has 5 neighbours
-            GridVertex {
-                horizontal: h,
-                vertical: _,
-            } if *h == 0 => GridNeighbourEdgeSet::LeftEdge([
-                (vert.up(), vert.right()),
-                (vert.right(), vert.down_right()),
-                (vert.down_right(), vert.down()),
-            ]),
-            GridVertex {
-                horizontal: h,
-                vertical: _,
-            } if *h == W - 1 => GridNeighbourEdgeSet::RightEdge([
-                (vert.down(), vert.left()),
-                (vert.left(), vert.up_left()),
-                (vert.up_left(), vert.up()),
-            ]),
-            GridVertex {
-                horizontal: _,
-                vertical: v,
-            } if *v == 0 => GridNeighbourEdgeSet::TopEdge([
-                (vert.right(), vert.down_right()),
-                (vert.down_right(), vert.down()),
-                (vert.down(), vert.left()),
-            ]),
-            GridVertex {
-                horizontal: _,
-                vertical: v,
-            } if *v == H - 1 => GridNeighbourEdgeSet::BottomEdge([
-                (vert.left(), vert.up_left()),
-                (vert.up_left(), vert.up()),
-                (vert.up(), vert.right()),
-            ]),
-            // Otherwise, the vertex has 8 neighbours
-            _ => GridNeighbourEdgeSet::Centre([
-                (vert.up(), vert.right()),
-                (vert.right(), vert.down_right()),
-                (vert.down_right(), vert.down()),
-                (vert.down(), vert.left()),
-                (vert.left(), vert.up_left()),
-                (vert.up_left(), vert.up()),
-            ]),
+    fn set_vert_colours(&mut self, vert: &GridVertex, colours: (Colour, Colour)) {
+        self.colours[vert.vertical][vert.horizontal] = colours;
+    }
+
+    fn get_vert_colour(&self, vert: &GridVertex) -> Colour {
+        self.vertex_colours[vert.vertical][vert.horizontal]
+    }
+
+    fn set_vert_colour(&mut self, vert: &GridVertex, colour: Colour) {
+        self.vertex_colours[vert.vertical][vert.horizontal] = colour;
+    }
+
+    fn get_vert_flipped(&self, vert: &GridVertex) -> bool {
+        self.flipped[vert.vertical][vert.horizontal]
+    }
+
+    fn set_vert_flipped(&mut self, vert: &GridVertex, flipped: bool) {
+        self.flipped[vert.vertical][vert.horizontal] = flipped;
+    }
+
+    fn get_vert_palette_indices(&self, vert: &GridVertex) -> (u8, u8) {
+        self.palette_indices[vert.vertical][vert.horizontal]
+    }
+
+    fn set_vert_palette_indices(&mut self, vert: &GridVertex, indices: (u8, u8)) {
+        self.palette_indices[vert.vertical][vert.horizontal] = indices;
+    }
+
+    fn get_palette(&self) -> &[Colour] {
+        &self.palette
+    }
+
+    fn set_palette_colour(&mut self, index: usize, colour: Colour) {
+        self.palette[index] = colour;
+    }
+
+    fn get_colour_mode(&self) -> ColourMode {
+        self.colour_mode
+    }
+}
+
+/// Access to the vertices and colours of a grid, the grid algorithms are written against this so
+/// that they are shared by GridImage and DynGridImage
+trait GridStorage {
+    /// Gets the number of vertices in each axis
+    fn get_grid_size(&self) -> (usize, usize);
+
+    fn get_vert_position(&self, vert: &GridVertex) -> Point;
+
+    fn set_vert_position(&mut self, vert: &GridVertex, position: Point);
+
+    /// Gets the colours of the upper and lower triangles of the cell whose top left vertex is
+    /// `vert`
+    fn get_vert_colours(&self, vert: &GridVertex) -> (Colour, Colour);
+
+    fn set_vert_colours(&mut self, vert: &GridVertex, colours: (Colour, Colour));
+
+    /// Gets the colour of a vertex, which is used when shading with ColourMode::Gouraud
+    fn get_vert_colour(&self, vert: &GridVertex) -> Colour;
+
+    fn set_vert_colour(&mut self, vert: &GridVertex, colour: Colour);
+
+    /// Gets whether the cell whose top left vertex is `vert` has its diagonal flipped
+    fn get_vert_flipped(&self, vert: &GridVertex) -> bool;
+
+    fn set_vert_flipped(&mut self, vert: &GridVertex, flipped: bool);
+
+    /// Gets the palette indices of the upper and lower triangles of the cell whose top left
+    /// vertex is `vert`, which are used when colouring with ColourMode::Palette
+    fn get_vert_palette_indices(&self, vert: &GridVertex) -> (u8, u8);
+
+    fn set_vert_palette_indices(&mut self, vert: &GridVertex, indices: (u8, u8));
+
+    fn get_palette(&self) -> &[Colour];
+
+    fn set_palette_colour(&mut self, index: usize, colour: Colour);
+
+    fn get_colour_mode(&self) -> ColourMode;
+}
+
+impl GridVertex {
+    /// Gets a random vertex which isn't on an edge of a grid with `size` vertices in each axis
+    fn random_inner(size: (usize, usize)) -> GridVertex {
+        GridVertex {
+            horizontal: (rand::random::<usize>() % (size.0 - 2)) + 1,
+            vertical: (rand::random::<usize>() % (size.1 - 2)) + 1,
         }
     }
 
-    /// Gets a random point within a vertex's surrounding polygon
-    fn get_random_point_in_vertex_polygon(&self, vert: &GridVertex) -> Point {
-        // The goal is to pick one of the triangles making up the polygon
-        // (randomly, relative to area), and then within that triangle, pick a random point,
-        // respective to https://mathworld.wolfram.com/TrianglePointPicking.html
-        // Gets the horizontal and vertical index of the vertex in the grid respectively
+    /// Gets a random vertex, including those on the edges of the grid
+    fn random(size: (usize, usize)) -> GridVertex {
+        GridVertex {
+            horizontal: rand::random::<usize>() % size.0,
+            vertical: rand::random::<usize>() % size.1,
+        }
+    }
 
-        // Maps a pair of vertices to their respective positions
-        let vert_point_map = |(left, right): (GridVertex, GridVertex)| {
-            (
-                self.get_vert_position(&left),
-                self.get_vert_position(&right),
-            )
-        };
+    /// Gets a random vertex on an edge of a grid with `size` vertices in each axis, which isn't a
+    /// corner
+    fn random_boundary(size: (usize, usize)) -> GridVertex {
+        let (columns, rows) = (size.0 - 2, size.1 - 2);
+        // Picks one of the top and bottom edges' vertices, then one of the left and right edges'
+        let index = rand::random::<usize>() % (2 * columns + 2 * rows);
+        if index < 2 * columns {
+            let vertical = if index.is_multiple_of(2) { 0 } else { size.1 - 1 };
+            GridVertex::new(index / 2 + 1, vertical)
+        } else {
+            let index = index - 2 * columns;
+            let horizontal = if index.is_multiple_of(2) { 0 } else { size.0 - 1 };
+            GridVertex::new(horizontal, index / 2 + 1)
+        }
+    }
 
-        // Gets the polygon surrounding the vert and uses it to generate a random point
-        match self.neighbour_edge_set(vert) {
-            GridNeighbourEdgeSet::Centre(e) => {
-                Polygon::new(self.get_vert_position(vert), e.map(vert_point_map)).get_random_point()
-            }
-            GridNeighbourEdgeSet::TopLeftCorner(e) | GridNeighbourEdgeSet::BottomRightCorner(e) => {
-                Polygon::new(self.get_vert_position(vert), e.map(vert_point_map)).get_random_point()
-            }
-            GridNeighbourEdgeSet::TopRightCorner(e) | GridNeighbourEdgeSet::BottomLeftCorner(e) => {
-                Polygon::new(self.get_vert_position(vert), e.map(vert_point_map)).get_random_point()
-            }
-            GridNeighbourEdgeSet::LeftEdge(e)
-            | GridNeighbourEdgeSet::TopEdge(e)
-            | GridNeighbourEdgeSet::RightEdge(e)
-            | GridNeighbourEdgeSet::BottomEdge(e) => {
-                Polygon::new(self.get_vert_position(vert), e.map(vert_point_map)).get_random_point()
+    /// Gets the top left vertex of a random cell, i.e. any vertex not on the right or bottom edge
+    fn random_cell(size: (usize, usize)) -> GridVertex {
+        GridVertex {
+            horizontal: rand::random::<usize>() % (size.0 - 1),
+            vertical: rand::random::<usize>() % (size.1 - 1),
+        }
+    }
+}
+
+/// Gets the flat colours of the upper and lower triangles of the cell whose top left vertex is
+/// `vert`, from the palette with ColourMode::Palette and from the cell's colours otherwise
+fn cell_colours<G: GridStorage>(grid: &G, vert: &GridVertex) -> (Colour, Colour) {
+    match grid.get_colour_mode() {
+        ColourMode::Palette => {
+            let (upper, lower) = grid.get_vert_palette_indices(vert);
+            let palette = grid.get_palette();
+            (palette[upper as usize], palette[lower as usize])
+        }
+        ColourMode::Flat | ColourMode::Gouraud => grid.get_vert_colours(vert),
+    }
+}
+
+/// Gives every triangle the index of the palette colour nearest to its flat colour
+fn reindex_palette<G: GridStorage>(grid: &mut G) {
+    let (width, height) = grid.get_grid_size();
+    for vertical in 0..height {
+        for horizontal in 0..width {
+            let vert = GridVertex::new(horizontal, vertical);
+            let (upper, lower) = grid.get_vert_colours(&vert);
+            let indices = (
+                upper.get_nearest(grid.get_palette()) as u8,
+                lower.get_nearest(grid.get_palette()) as u8,
+            );
+            grid.set_vert_palette_indices(&vert, indices);
+        }
+    }
+}
+
+/// Asserts that a palette has between 1 and 256 colours, so that it can be indexed by a u8
+fn assert_palette_size(palette: &[Colour]) {
+    assert!(
+        !palette.is_empty() && palette.len() <= 256,
+        "The palette must have between 1 and 256 colours"
+    );
+}
+
+/// Counts the triangles that are inverted or have zero area
+fn count_invalid_triangles<G: Triangulated>(image: &G) -> usize {
+    let mut invalid = 0;
+    image.get_triangles(|first, second, third, _| {
+        if Tri::new(first, second, third).orientation() <= 0 {
+            invalid += 1;
+        }
+    });
+    invalid
+}
+
+/// Gets the two triangles of the cell whose top left vertex is `vert`, the first includes the
+/// top edge of the cell and the second includes the bottom edge. The cell is split along its
+/// diagonal from top left to bottom right, or from top right to bottom left if it is flipped,
+/// and the vertices of both triangles are in the same (clockwise on screen) order
+fn cell_triangles<G: GridStorage>(grid: &G, vert: &GridVertex) -> [[GridVertex; 3]; 2] {
+    let [v, right, down_right, down] = [*vert, vert.right(), vert.down_right(), vert.down()];
+    if grid.get_vert_flipped(vert) {
+        [[v, right, down], [right, down_right, down]]
+    } else {
+        [[v, right, down_right], [v, down_right, down]]
+    }
+}
+
+/// Whether both of the triangles of the cell whose top left vertex is `vert` are valid, i.e.
+/// neither is inverted nor has zero area
+fn is_cell_valid<G: GridStorage>(grid: &G, vert: &GridVertex) -> bool {
+    cell_triangles(grid, vert).iter().all(|triangle| {
+        let [first, second, third] = triangle.map(|corner| grid.get_vert_position(&corner));
+        Tri::new(first, second, third).orientation() > 0
+    })
+}
+
+/// Gets the top left vertex of every cell that includes a vertex, i.e. the cell of the vertex and
+/// the cells left, up and up left of it, where they exist
+fn incident_cells<G: GridStorage>(grid: &G, vert: &GridVertex) -> Vec<GridVertex> {
+    let (width, height) = grid.get_grid_size();
+    let mut cells = Vec::new();
+    if vert.horizontal < width - 1 && vert.vertical < height - 1 {
+        cells.push(*vert);
+    }
+    if vert.horizontal > 0 && vert.vertical < height - 1 {
+        cells.push(vert.left());
+    }
+    if vert.horizontal < width - 1 && vert.vertical > 0 {
+        cells.push(vert.up());
+    }
+    if vert.horizontal > 0 && vert.vertical > 0 {
+        cells.push(vert.up_left());
+    }
+    cells
+}
+
+/// Whether every triangle that includes a vertex is valid
+fn is_vertex_valid<G: GridStorage>(grid: &G, vert: &GridVertex) -> bool {
+    incident_cells(grid, vert)
+        .iter()
+        .all(|cell| is_cell_valid(grid, cell))
+}
+
+/// Gets the edge opposite a vertex in each of the triangles that include it, together these are
+/// the boundary of the polygon surrounding the vertex, in no particular order
+fn neighbour_edges<G: GridStorage>(grid: &G, vert: &GridVertex) -> Vec<(GridVertex, GridVertex)> {
+    let mut edges = Vec::new();
+    for cell in incident_cells(grid, vert) {
+        for triangle in cell_triangles(grid, &cell) {
+            // The edge keeps the order of the triangle's vertices
+            if let Some(i) = triangle.iter().position(|corner| corner == vert) {
+                edges.push((triangle[(i + 1) % 3], triangle[(i + 2) % 3]));
             }
         }
     }
+    edges
+}
 
-    // /// Given a vertex and delta, tries to 'apply' the delta to the vertex's position, limiting
-    // /// it if the 'applied' delta is out of bounds.
-    // /// Note, this doesn't actually modify the structure, it just returns the new position
-    // fn apply_delta_to_vertex(&self, vert: &GridVertex, delta: (u16, u16)) -> Point {}
+/// Gets the polygon surrounding a vertex, formed by the triangles that include it
+fn get_vertex_polygon<G: GridStorage>(grid: &G, vert: &GridVertex) -> Polygon {
+    let edges = neighbour_edges(grid, vert)
+        .into_iter()
+        .map(|(left, right)| {
+            (
+                grid.get_vert_position(&left),
+                grid.get_vert_position(&right),
+            )
+        })
+        .collect();
+    Polygon::new(grid.get_vert_position(vert), edges)
 }
+
+// /// Given a vertex and delta, tries to 'apply' the delta to the vertex's position, limiting
+// /// it if the 'applied' delta is out of bounds.
+// /// Note, this doesn't actually modify the structure, it just returns the new position
+// fn apply_delta_to_vertex(&self, vert: &GridVertex, delta: (u16, u16)) -> Point {}
@@ -1,23 +1,21 @@
-use super::{Colour, GridImage, GridVertex};
+use super::validation::validate;
+use super::{
+    get_vertex_polygon, is_cell_valid, is_vertex_valid, Colour, GridImage, GridStorage, GridVertex,
+    Point,
+};
+use crate::colour::ColourSpace;
+use crate::images::{ColourMode, StructureMutation};
 use rand::{random, Rng};
 use std::cmp::{max, min};
-use std::ops::RangeInclusive;
+use std::f32::consts::PI;
 
-fn mutate_colour(colour: Colour, mutation_rate: f32) -> Colour {
+/// The number of random points tried when moving a vertex, before leaving it where it is
+const MOVE_ATTEMPTS: usize = 8;
+
+/// Mutates the colour with probability `mutation_rate`, see `Colour::perturb`
+fn mutate_colour(colour: Colour, mutation_rate: f32, space: ColourSpace) -> Colour {
     if random::<f32>() < mutation_rate {
-        let mut thread_rng = rand::thread_rng();
-
-        Colour {
-            r: thread_rng.gen_range(
-                max(0_i32, colour.r as i32 - 20_i32)..=min(colour.r as i32 + 20_i32, 255_i32),
-            ) as u8,
-            g: thread_rng.gen_range(
-                max(0_i32, colour.g as i32 - 20_i32)..=min(colour.g as i32 + 20_i32, 255_i32),
-            ) as u8,
-            b: thread_rng.gen_range(
-                max(0_i32, colour.b as i32 - 20_i32)..=min(colour.b as i32 + 20_i32, 255_i32),
-            ) as u8,
-        }
+        colour.perturb(space)
     } else {
         colour
     }
@@ -35,6 +33,8 @@ impl<const W: usize, const H: usize> GridImage<W, H> {
     ///              circle is uncapped. This is an intersection with the vertex's surrounding
     ///              polygon for valid places to move the vertex to
     ///
+    /// The vertex is left where it is if no valid point is found
+    ///
     /// # Examples
     /// ```
     /// use image_lib::images::grid::GridImage;
@@ -43,26 +43,68 @@ impl<const W: usize, const H: usize> GridImage<W, H> {
     /// image.mutate_structure(&image.get_random_inner_vertex(), None);
     /// ```
     pub fn mutate_structure(&mut self, vert: &GridVertex, radius: Option<u32>) {
-        // Picks a random point to move the vertex to
-        let point = self.get_random_point_in_vertex_polygon(vert);
-        // Mutates the grid image's position function to change the position of the vertex
-        self.vertex_positions[vert.vertical][vert.horizontal] = point;
+        mutate_structure(self, vert, radius);
+    }
+
+    /// Moves the vertex by a normally distributed offset in each axis with standard deviation
+    /// `sigma`, the vertex must stay within its surrounding polygon, so it is left where it is if
+    /// no valid offset is found
+    pub fn mutate_structure_gaussian(&mut self, vert: &GridVertex, sigma: f32) {
+        mutate_structure_gaussian(self, vert, sigma);
     }
 
-    /// Mutates the colour set of the image
-    pub fn mutate_colours(&mut self, mutation_rate: f32) {
-        self.colours.map(|row| {
-            row.map(|column| {
-                // Chooses our base
-                let (left, right) = column.clone();
+    /// Slides a vertex on an edge of the grid along the edge, between its neighbours on the edge,
+    /// moving it by `structure` in the same way as the structure mutations. Corners are fixed, so
+    /// the image always covers the whole of its dimensions
+    pub fn slide_boundary_vertex(&mut self, vert: &GridVertex, structure: StructureMutation) {
+        slide_boundary_vertex(self, vert, structure);
+    }
 
-                // Randomly mutates the colour according to the `mutation_rate`
-                (
-                    mutate_colour(left, mutation_rate),
-                    mutate_colour(right, mutation_rate),
-                )
-            })
-        });
+    /// Moves a random vertex by `structure`, any vertex but the corners may be moved, the
+    /// vertices on the edges of the grid are slid along their edge
+    pub fn mutate_random_vertex(&mut self, structure: StructureMutation) {
+        mutate_random_vertex(self, structure);
+    }
+
+    /// Flips the diagonal that the cell whose top left vertex is `vert` is split along, the
+    /// diagonal is only flipped if both of the cell's new triangles are valid, i.e. if the cell
+    /// is convex. Returns whether the diagonal was flipped
+    pub fn flip_diagonal(&mut self, vert: &GridVertex) -> bool {
+        flip_diagonal(self, vert)
+    }
+
+    /// Mutates the colour of one of the two triangles in the cell whose top left vertex is `vert`
+    /// in `space`, `upper` selects the triangle including the top and right edge of the cell
+    pub fn mutate_triangle_colour(&mut self, vert: &GridVertex, upper: bool, space: ColourSpace) {
+        mutate_triangle_colour(self, vert, upper, space);
+    }
+
+    /// Mutates the colour of a vertex in `space`, which is used when shading with
+    /// ColourMode::Gouraud
+    pub fn mutate_vertex_colour(&mut self, vert: &GridVertex, space: ColourSpace) {
+        mutate_vertex_colour(self, vert, space);
+    }
+
+    /// Gives one of the two triangles in the cell whose top left vertex is `vert` a random colour
+    /// of the palette, `upper` selects the triangle including the top and right edge of the cell
+    pub fn mutate_palette_index(&mut self, vert: &GridVertex, upper: bool) {
+        mutate_palette_index(self, vert, upper);
+    }
+
+    /// Mutates the colour of the palette at `index` in `space`, which changes every triangle
+    /// using it, see `Colour::perturb`
+    pub fn mutate_palette_colour(&mut self, index: usize, space: ColourSpace) {
+        mutate_palette_colour(self, index, space);
+    }
+
+    /// Mutates a random colour of the image in `space`, that is used by its colour mode
+    pub fn mutate_random_colour(&mut self, space: ColourSpace) {
+        mutate_random_colour(self, space);
+    }
+
+    /// Mutates the colour set of the image, that is used by its colour mode
+    pub fn mutate_colours(&mut self, mutation_rate: f32, space: ColourSpace) {
+        mutate_colours(self, mutation_rate, space);
     }
 }
 
@@ -73,56 +115,688 @@ impl<const W: usize, const H: usize> GridImage<W, H> {
         right: &GridImage<W, H>,
         mutation_rate: f32,
     ) -> GridImage<W, H> {
-        // Randomly chooses to pick the left or right vertex position set
-        let vertex_positions = (if random() { left } else { right })
-            .get_vertex_positions()
-            .clone();
-
-        // Breeds the colours
-        let mut x: isize = -1;
-        let mut y: isize = -1;
-        let colours = [0; H].map(|_| {
-            y += 1;
-            x = -1;
-            [0; W].map(|_| {
-                x += 1;
-                let left_colour = left.get_colours()[y as usize][x as usize].clone();
-                let right_colour = right.get_colours()[y as usize][x as usize].clone();
-
-                // Randomly mutates the colour according to the `mutation_rate`
+        GridImage::breed_traced(
+            left,
+            right,
+            mutation_rate,
+            StructureMutation::Uniform(None),
+            ColourSpace::Rgb,
+            |_| {},
+        )
+    }
+
+    /// Breeds the images in the same way as `breed`, but vertices are moved by `structure` and
+    /// colours are mutated in `space`, `on_operator` is called with the name of each operator
+    /// that is applied to the child
+    pub fn breed_traced<F>(
+        left: &GridImage<W, H>,
+        right: &GridImage<W, H>,
+        mutation_rate: f32,
+        structure: StructureMutation,
+        space: ColourSpace,
+        on_operator: F,
+    ) -> GridImage<W, H>
+    where
+        F: FnMut(&'static str),
+    {
+        breed_traced(left, right, mutation_rate, structure, space, on_operator)
+    }
+
+    /// Breeds the structure of the images, the child has the vertex positions of one of the
+    /// parents, chosen at random, and each cell's diagonal from either parent, with structure
+    /// mutations applied. The child's appearance is that of the chosen parent
+    pub(super) fn breed_structure<F>(
+        left: &GridImage<W, H>,
+        right: &GridImage<W, H>,
+        mutation_rate: f32,
+        structure: StructureMutation,
+        on_operator: &mut F,
+    ) -> GridImage<W, H>
+    where
+        F: FnMut(&'static str),
+    {
+        breed_structure(left, right, mutation_rate, structure, on_operator)
+    }
+
+    /// Replaces the appearance of the image with a crossover of the parents' appearance, with
+    /// colour mutations applied in `space`. Only the colours used by the image's colour mode are
+    /// bred
+    pub(super) fn breed_appearance<F>(
+        &mut self,
+        left: &GridImage<W, H>,
+        right: &GridImage<W, H>,
+        mutation_rate: f32,
+        space: ColourSpace,
+        on_operator: &mut F,
+    ) where
+        F: FnMut(&'static str),
+    {
+        breed_appearance(self, left, right, mutation_rate, space, on_operator);
+    }
+}
+
+/// See `GridImage::mutate_structure`
+pub(super) fn mutate_structure<G: GridStorage>(
+    grid: &mut G,
+    vert: &GridVertex,
+    radius: Option<u32>,
+) {
+    let origin = grid.get_vert_position(vert);
+    let polygon = get_vertex_polygon(grid, vert);
+    let within = |point: Point| match radius {
+        Some(radius) => {
+            (point.x as f64 - origin.x as f64).hypot(point.y as f64 - origin.y as f64)
+                <= radius as f64
+        }
+        None => true,
+    };
+    match radius {
+        // A circle smaller than the polygon is sampled directly, so that small radii don't
+        // reject most of the points picked in the polygon. Rounding may move the point out of
+        // the circle, so it is checked again
+        Some(radius) if PI as f64 * (radius as f64).powi(2) < polygon.get_area() as f64 => {
+            move_vertex(grid, vert, || {
+                let (distance, angle) = (radius as f32 * random::<f32>().sqrt(), random::<f32>());
+                let angle = 2.0 * PI * angle;
+                offset_point(origin, distance * angle.cos(), distance * angle.sin())
+                    .filter(|&point| within(point))
+            });
+        }
+        // Otherwise a random point of the polygon is picked, and rejected if it is outside the
+        // circle
+        _ => move_vertex(grid, vert, || {
+            Some(polygon.get_random_point()).filter(|&point| within(point))
+        }),
+    }
+}
+
+/// See `GridImage::mutate_structure_gaussian`
+pub(super) fn mutate_structure_gaussian<G: GridStorage>(
+    grid: &mut G,
+    vert: &GridVertex,
+    sigma: f32,
+) {
+    let origin = grid.get_vert_position(vert);
+    move_vertex(grid, vert, || {
+        offset_point(origin, sigma * standard_normal(), sigma * standard_normal())
+    });
+}
+
+/// See `GridImage::slide_boundary_vertex`
+pub(super) fn slide_boundary_vertex<G: GridStorage>(
+    grid: &mut G,
+    vert: &GridVertex,
+    structure: StructureMutation,
+) {
+    let (width, height) = grid.get_grid_size();
+    let on_horizontal_edge = vert.vertical == 0 || vert.vertical == height - 1;
+    let on_vertical_edge = vert.horizontal == 0 || vert.horizontal == width - 1;
+    assert!(
+        on_horizontal_edge != on_vertical_edge,
+        "The vertex must be on an edge of the grid, but not a corner"
+    );
+
+    // The vertex slides horizontally between its left and right neighbours on the top and bottom
+    // edges, and vertically between its up and down neighbours on the left and right edges
+    let origin = grid.get_vert_position(vert);
+    let (previous, value, next) = if on_horizontal_edge {
+        (
+            grid.get_vert_position(&vert.left()).x,
+            origin.x,
+            grid.get_vert_position(&vert.right()).x,
+        )
+    } else {
+        (
+            grid.get_vert_position(&vert.up()).y,
+            origin.y,
+            grid.get_vert_position(&vert.down()).y,
+        )
+    };
+    let (previous, value, next) = (previous as i64, value as i64, next as i64);
+    move_vertex(grid, vert, || {
+        let slid = match structure {
+            StructureMutation::Uniform(radius) => {
+                let radius = radius.map_or(i64::MAX, |radius| radius as i64);
+                let range = max(previous + 1, value.saturating_sub(radius))
+                    ..=min(next - 1, value.saturating_add(radius));
+                if range.is_empty() {
+                    return None;
+                }
+                rand::thread_rng().gen_range(range)
+            }
+            StructureMutation::Gaussian(sigma) => {
+                (value as f32 + sigma * standard_normal()).round() as i64
+            }
+        };
+        if slid <= previous || slid >= next {
+            return None;
+        }
+        Some(if on_horizontal_edge {
+            Point::new(slid as u16, origin.y)
+        } else {
+            Point::new(origin.x, slid as u16)
+        })
+    });
+}
+
+/// See `GridImage::mutate_random_vertex`
+pub(super) fn mutate_random_vertex<G: GridStorage>(grid: &mut G, structure: StructureMutation) {
+    let (width, height) = grid.get_grid_size();
+    // Every vertex but the corners is equally likely to be moved
+    let inner = (width - 2) * (height - 2);
+    let boundary = 2 * (width - 2) + 2 * (height - 2);
+    if rand::thread_rng().gen_range(0..inner + boundary) < inner {
+        mutate_structure_by(grid, &GridVertex::random_inner((width, height)), structure);
+    } else {
+        slide_boundary_vertex(
+            grid,
+            &GridVertex::random_boundary((width, height)),
+            structure,
+        );
+    }
+}
+
+/// Moves the vertex by the mutation, see `StructureMutation`
+fn mutate_structure_by<G: GridStorage>(
+    grid: &mut G,
+    vert: &GridVertex,
+    structure: StructureMutation,
+) {
+    match structure {
+        StructureMutation::Uniform(radius) => mutate_structure(grid, vert, radius),
+        StructureMutation::Gaussian(sigma) => mutate_structure_gaussian(grid, vert, sigma),
+    }
+}
+
+/// Moves the vertex to the first point given by `pick` that is within the vertex's surrounding
+/// polygon and doesn't invert any of its triangles, `pick` may give None to reject a point.
+/// When cells are flipped the polygon needn't be convex, so a point within it isn't always valid
+fn move_vertex<G, F>(grid: &mut G, vert: &GridVertex, mut pick: F)
+where
+    G: GridStorage,
+    F: FnMut() -> Option<Point>,
+{
+    let original = grid.get_vert_position(vert);
+    let polygon = get_vertex_polygon(grid, vert);
+    for _ in 0..MOVE_ATTEMPTS {
+        let point = match pick() {
+            Some(point) if polygon.contains(point) => point,
+            _ => continue,
+        };
+        // Mutates the grid image's position function to change the position of the vertex
+        grid.set_vert_position(vert, point);
+        if is_vertex_valid(grid, vert) {
+            return;
+        }
+        grid.set_vert_position(vert, original);
+    }
+}
+
+/// Offsets the point by (x, y), rounded to the nearest point, if the result is within the image
+fn offset_point(point: Point, x: f32, y: f32) -> Option<Point> {
+    let offset = |value: u16, delta: f32| {
+        let value = (value as f32 + delta).round();
+        (0.0..=u16::MAX as f32)
+            .contains(&value)
+            .then_some(value as u16)
+    };
+    Some(Point::new(offset(point.x, x)?, offset(point.y, y)?))
+}
+
+/// Samples the standard normal distribution with the Box-Muller transform
+fn standard_normal() -> f32 {
+    // The first sample is in (0, 1] so that its logarithm is finite
+    let (first, second) = (1.0 - random::<f32>(), random::<f32>());
+    (-2.0 * first.ln()).sqrt() * (2.0 * PI * second).cos()
+}
+
+/// See `GridImage::flip_diagonal`
+pub(super) fn flip_diagonal<G: GridStorage>(grid: &mut G, vert: &GridVertex) -> bool {
+    let flipped = grid.get_vert_flipped(vert);
+    grid.set_vert_flipped(vert, !flipped);
+    if is_cell_valid(grid, vert) {
+        true
+    } else {
+        grid.set_vert_flipped(vert, flipped);
+        false
+    }
+}
+
+/// See `GridImage::mutate_triangle_colour`
+pub(super) fn mutate_triangle_colour<G: GridStorage>(
+    grid: &mut G,
+    vert: &GridVertex,
+    upper: bool,
+    space: ColourSpace,
+) {
+    let mut colours = grid.get_vert_colours(vert);
+    if upper {
+        colours.0 = mutate_colour(colours.0, 1.0, space);
+    } else {
+        colours.1 = mutate_colour(colours.1, 1.0, space);
+    }
+    grid.set_vert_colours(vert, colours);
+}
+
+/// See `GridImage::mutate_vertex_colour`
+pub(super) fn mutate_vertex_colour<G: GridStorage>(
+    grid: &mut G,
+    vert: &GridVertex,
+    space: ColourSpace,
+) {
+    let colour = grid.get_vert_colour(vert);
+    grid.set_vert_colour(vert, mutate_colour(colour, 1.0, space));
+}
+
+/// See `GridImage::mutate_palette_index`
+pub(super) fn mutate_palette_index<G: GridStorage>(grid: &mut G, vert: &GridVertex, upper: bool) {
+    let index = rand::thread_rng().gen_range(0..grid.get_palette().len()) as u8;
+    let mut indices = grid.get_vert_palette_indices(vert);
+    if upper {
+        indices.0 = index;
+    } else {
+        indices.1 = index;
+    }
+    grid.set_vert_palette_indices(vert, indices);
+}
+
+/// See `GridImage::mutate_palette_colour`
+pub(super) fn mutate_palette_colour<G: GridStorage>(
+    grid: &mut G,
+    index: usize,
+    space: ColourSpace,
+) {
+    let colour = grid.get_palette()[index];
+    grid.set_palette_colour(index, mutate_colour(colour, 1.0, space));
+}
+
+/// See `GridImage::mutate_random_colour`
+pub(super) fn mutate_random_colour<G: GridStorage>(grid: &mut G, space: ColourSpace) {
+    match grid.get_colour_mode() {
+        ColourMode::Flat => {
+            let cell = GridVertex::random_cell(grid.get_grid_size());
+            mutate_triangle_colour(grid, &cell, random(), space);
+        }
+        ColourMode::Gouraud => {
+            let vert = GridVertex::random(grid.get_grid_size());
+            mutate_vertex_colour(grid, &vert, space);
+        }
+        // Either a triangle picks another colour or a colour of the palette is changed
+        ColourMode::Palette => {
+            if random() {
+                let cell = GridVertex::random_cell(grid.get_grid_size());
+                mutate_palette_index(grid, &cell, random());
+            } else {
+                let index = rand::thread_rng().gen_range(0..grid.get_palette().len());
+                mutate_palette_colour(grid, index, space);
+            }
+        }
+    }
+}
+
+/// See `GridImage::mutate_colours`
+pub(super) fn mutate_colours<G: GridStorage>(grid: &mut G, mutation_rate: f32, space: ColourSpace) {
+    let (width, height) = grid.get_grid_size();
+    for vertical in 0..height {
+        for horizontal in 0..width {
+            let vert = GridVertex::new(horizontal, vertical);
+            match grid.get_colour_mode() {
+                ColourMode::Flat => {
+                    // Randomly mutates the colours according to the `mutation_rate`
+                    let (upper, lower) = grid.get_vert_colours(&vert);
+                    grid.set_vert_colours(
+                        &vert,
+                        (
+                            mutate_colour(upper, mutation_rate, space),
+                            mutate_colour(lower, mutation_rate, space),
+                        ),
+                    );
+                }
+                ColourMode::Gouraud => {
+                    let colour = grid.get_vert_colour(&vert);
+                    grid.set_vert_colour(&vert, mutate_colour(colour, mutation_rate, space));
+                }
+                ColourMode::Palette => {
+                    for upper in [true, false] {
+                        if random::<f32>() < mutation_rate {
+                            mutate_palette_index(grid, &vert, upper);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if grid.get_colour_mode() == ColourMode::Palette {
+        for index in 0..grid.get_palette().len() {
+            let colour = grid.get_palette()[index];
+            grid.set_palette_colour(index, mutate_colour(colour, mutation_rate, space));
+        }
+    }
+}
+
+/// See `GridImage::breed_traced`
+pub(super) fn breed_traced<G, F>(
+    left: &G,
+    right: &G,
+    mutation_rate: f32,
+    structure: StructureMutation,
+    space: ColourSpace,
+    mut on_operator: F,
+) -> G
+where
+    G: GridStorage + Clone,
+    F: FnMut(&'static str),
+{
+    let mut image = breed_structure(left, right, mutation_rate, structure, &mut on_operator);
+    breed_appearance(
+        &mut image,
+        left,
+        right,
+        mutation_rate,
+        space,
+        &mut on_operator,
+    );
+    image
+}
+
+/// See `GridImage::breed_structure`
+pub(super) fn breed_structure<G, F>(
+    left: &G,
+    right: &G,
+    mutation_rate: f32,
+    structure: StructureMutation,
+    on_operator: &mut F,
+) -> G
+where
+    G: GridStorage + Clone,
+    F: FnMut(&'static str),
+{
+    // Randomly chooses to pick the left or right vertex position set
+    let from_left = random();
+    on_operator(if from_left {
+        "structure_from_left"
+    } else {
+        "structure_from_right"
+    });
+    let (mut image, other) = if from_left {
+        (left.clone(), right)
+    } else {
+        (right.clone(), left)
+    };
+
+    // Each cell randomly takes its diagonal from either parent, as the vertex positions are from
+    // the chosen parent the other parent's diagonal is only taken if it is valid for them
+    on_operator("diagonal_crossover");
+    let (width, height) = image.get_grid_size();
+    for vertical in 0..(height - 1) {
+        for horizontal in 0..(width - 1) {
+            let cell = GridVertex::new(horizontal, vertical);
+            if random() && image.get_vert_flipped(&cell) != other.get_vert_flipped(&cell) {
+                flip_diagonal(&mut image, &cell);
+            }
+        }
+    }
+
+    // There are W * H vertices so we mutate W * H times
+    // with chance of mutation in each case being `mutation_rate`, and likewise for the diagonals
+    for _ in 0..(width * height) {
+        if random::<f32>() < mutation_rate {
+            let vert = GridVertex::random_inner((width, height));
+            mutate_structure_by(&mut image, &vert, structure);
+            on_operator("mutate_structure");
+        }
+        if random::<f32>() < mutation_rate {
+            let cell = GridVertex::random_cell((width, height));
+            if flip_diagonal(&mut image, &cell) {
+                on_operator("flip_diagonal");
+            }
+        }
+    }
+
+    // The vertices on the edges, excluding the corners, are slid along the edges with the same
+    // chance of mutation
+    for _ in 0..(2 * (width - 2) + 2 * (height - 2)) {
+        if random::<f32>() < mutation_rate {
+            let vert = GridVertex::random_boundary((width, height));
+            slide_boundary_vertex(&mut image, &vert, structure);
+            on_operator("slide_boundary_vertex");
+        }
+    }
+
+    // Every structure operator keeps the mesh valid
+    debug_assert!(
+        !validate(left).is_empty() || !validate(right).is_empty() || validate(&image).is_empty(),
+        "Breeding valid parents gave a child with an invalid mesh"
+    );
+    image
+}
+
+/// See `GridImage::breed_appearance`
+pub(super) fn breed_appearance<G, F>(
+    image: &mut G,
+    left: &G,
+    right: &G,
+    mutation_rate: f32,
+    space: ColourSpace,
+    on_operator: &mut F,
+) where
+    G: GridStorage,
+    F: FnMut(&'static str),
+{
+    on_operator("colour_crossover");
+
+    match image.get_colour_mode() {
+        ColourMode::Gouraud => {
+            breed_vertex_colours(image, left, right, mutation_rate, space, on_operator);
+            return;
+        }
+        ColourMode::Palette => {
+            breed_palette(image, left, right, mutation_rate, space, on_operator);
+            return;
+        }
+        ColourMode::Flat => {}
+    }
+
+    // Breeds the colours
+    let (width, height) = image.get_grid_size();
+    for y in 0..height {
+        for x in 0..width {
+            let vert = GridVertex::new(x, y);
+            let left_colour = left.get_vert_colours(&vert);
+            let right_colour = right.get_vert_colours(&vert);
+
+            let upper = if random() {
+                left_colour.0
+            } else {
+                right_colour.0
+            };
+            let lower = if random() {
+                left_colour.1
+            } else {
+                right_colour.1
+            };
+
+            // Randomly mutates the colour according to the `mutation_rate`
+            let colours = (
+                mutate_colour(upper, mutation_rate, space),
+                mutate_colour(lower, mutation_rate, space),
+            );
+            for _ in 0..((colours.0 != upper) as u8 + (colours.1 != lower) as u8) {
+                on_operator("mutate_colour");
+            }
+            image.set_vert_colours(&vert, colours);
+        }
+    }
+}
+
+/// Uniform crossover of the parents' vertex colours, with colour mutations applied
+fn breed_vertex_colours<G, F>(
+    image: &mut G,
+    left: &G,
+    right: &G,
+    mutation_rate: f32,
+    space: ColourSpace,
+    on_operator: &mut F,
+) where
+    G: GridStorage,
+    F: FnMut(&'static str),
+{
+    let (width, height) = image.get_grid_size();
+    for y in 0..height {
+        for x in 0..width {
+            let vert = GridVertex::new(x, y);
+            let colour = if random() {
+                left.get_vert_colour(&vert)
+            } else {
+                right.get_vert_colour(&vert)
+            };
+            let mutated = mutate_colour(colour, mutation_rate, space);
+            if mutated != colour {
+                on_operator("mutate_colour");
+            }
+            image.set_vert_colour(&vert, mutated);
+        }
+    }
+}
+
+/// Uniform crossover of the parents' palette indices and of their palettes' colours, with each
+/// index having a `mutation_rate` chance of being changed and each palette colour the same chance
+/// of being mutated in `space`. The image keeps the size of its own palette, the colours of a
+/// parent with a smaller palette are only used where it has them
+fn breed_palette<G, F>(
+    image: &mut G,
+    left: &G,
+    right: &G,
+    mutation_rate: f32,
+    space: ColourSpace,
+    on_operator: &mut F,
+) where
+    G: GridStorage,
+    F: FnMut(&'static str),
+{
+    let size = image.get_palette().len();
+    let pick = |left: u8, right: u8| {
+        if (right as usize) < size && ((left as usize) >= size || random()) {
+            right
+        } else {
+            left
+        }
+    };
+    let (width, height) = image.get_grid_size();
+    for y in 0..height {
+        for x in 0..width {
+            let vert = GridVertex::new(x, y);
+            let (left_indices, right_indices) = (
+                left.get_vert_palette_indices(&vert),
+                right.get_vert_palette_indices(&vert),
+            );
+            image.set_vert_palette_indices(
+                &vert,
                 (
-                    mutate_colour(
-                        if random() {
-                            left_colour.0
-                        } else {
-                            right_colour.0
-                        },
-                        mutation_rate,
-                    ),
-                    mutate_colour(
-                        if random() {
-                            left_colour.1
-                        } else {
-                            right_colour.1
-                        },
-                        mutation_rate,
-                    ),
-                )
-            })
-        });
-
-        let mut image = GridImage {
-            vertex_positions,
-            colours,
+                    pick(left_indices.0, right_indices.0),
+                    pick(left_indices.1, right_indices.1),
+                ),
+            );
+            for upper in [true, false] {
+                if random::<f32>() < mutation_rate {
+                    mutate_palette_index(image, &vert, upper);
+                    on_operator("mutate_palette_index");
+                }
+            }
+        }
+    }
+
+    for index in 0..size {
+        let parent = match (
+            left.get_palette().get(index),
+            right.get_palette().get(index),
+        ) {
+            (Some(left), Some(right)) => {
+                if random() {
+                    *left
+                } else {
+                    *right
+                }
+            }
+            (Some(colour), None) | (None, Some(colour)) => *colour,
+            (None, None) => image.get_palette()[index],
         };
+        let colour = mutate_colour(parent, mutation_rate, space);
+        if colour != parent {
+            on_operator("mutate_palette_colour");
+        }
+        image.set_palette_colour(index, colour);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // There are W * H vertices so we mutate W * H times
-        // with chance of mutation in each case being `mutation_rate`
-        for _ in 0..(W * H) {
-            if random::<f32>() < mutation_rate {
-                image.mutate_structure(&image.get_random_inner_vertex(), None);
+    #[test]
+    fn structure_mutations_stay_in_range_and_keep_the_mesh_valid() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        for radius in [0, 1, 100, 5000, 50000] {
+            for _ in 0..1000 {
+                let vert = image.get_random_inner_vertex();
+                let origin = image.get_vert_position(&vert);
+                image.mutate_structure(&vert, Some(radius));
+                let point = image.get_vert_position(&vert);
+                let distance =
+                    (point.x as f64 - origin.x as f64).hypot(point.y as f64 - origin.y as f64);
+                assert!(distance <= radius as f64);
+            }
+            assert!(image.validate().is_empty());
+        }
+        for sigma in [0.5, 100.0, 5000.0, 50000.0] {
+            for _ in 0..1000 {
+                image.mutate_structure_gaussian(&image.get_random_inner_vertex(), sigma);
+            }
+            assert!(image.validate().is_empty());
+        }
+    }
+
+    #[test]
+    fn boundary_slides_keep_corners_and_edges() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        let uniform = image.clone();
+        let structures = [
+            StructureMutation::Uniform(None),
+            StructureMutation::Uniform(Some(500)),
+            StructureMutation::Gaussian(2000.0),
+        ];
+        for structure in structures {
+            for _ in 0..1000 {
+                image.slide_boundary_vertex(&image.get_random_boundary_vertex(), structure);
+            }
+            for (horizontal, vertical) in [(0, 0), (7, 0), (0, 5), (7, 5)] {
+                let corner = GridVertex::new(horizontal, vertical);
+                assert_eq!(
+                    image.get_vert_position(&corner),
+                    uniform.get_vert_position(&corner)
+                );
+            }
+            // Each edge vertex keeps the coordinate of its edge, and stays between its
+            // neighbours on the edge
+            for vertical in [0, 5] {
+                for horizontal in 1..7 {
+                    let vert = GridVertex::new(horizontal, vertical);
+                    let position = image.get_vert_position(&vert);
+                    assert_eq!(position.y, uniform.get_vert_position(&vert).y);
+                    assert!(image.get_vert_position(&vert.left()).x < position.x);
+                    assert!(position.x < image.get_vert_position(&vert.right()).x);
+                }
+            }
+            for horizontal in [0, 7] {
+                for vertical in 1..5 {
+                    let vert = GridVertex::new(horizontal, vertical);
+                    let position = image.get_vert_position(&vert);
+                    assert_eq!(position.x, uniform.get_vert_position(&vert).x);
+                    assert!(image.get_vert_position(&vert.up()).y < position.y);
+                    assert!(position.y < image.get_vert_position(&vert.down()).y);
+                }
             }
+            assert!(image.validate().is_empty());
         }
-        image
     }
 }
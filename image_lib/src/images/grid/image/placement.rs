@@ -0,0 +1,255 @@
+use super::initialise::box_blur;
+use super::{
+    get_vertex_polygon, is_vertex_valid, neighbour_edges, Colour, GridImage, GridStorage,
+    GridVertex, Point,
+};
+use crate::images::Triangulated;
+use std::str::FromStr;
+
+/// The number of rounds in which every inner vertex is moved towards the detail around it
+const PLACEMENT_ITERATIONS: usize = 8;
+/// The number of times a move is halved when it would take the vertex outside of its polygon or
+/// make one of its triangles invalid, before leaving the vertex where it is
+const PLACEMENT_ATTEMPTS: usize = 4;
+
+/// Where the vertices of an image are placed initially, according to a target
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VertexPlacement {
+    /// Vertices are moved towards strong edges in the target, found by the Sobel operator on its
+    /// luminance
+    Edges,
+    /// Vertices are moved towards salient regions of the target, those whose (slightly blurred)
+    /// colour differs most from the target's mean colour in CIELAB
+    Saliency,
+}
+
+impl FromStr for VertexPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "edges" => Ok(VertexPlacement::Edges),
+            "saliency" => Ok(VertexPlacement::Saliency),
+            _ => Err(format!("unknown vertex placement `{}`", s)),
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> GridImage<W, H> {
+    /// Moves the inner vertices towards the detail of the target, so that triangles are
+    /// concentrated where the detail is. Each round, every vertex is moved halfway towards the
+    /// centroid of its surrounding polygon, weighted by how much detail each pixel of the target
+    /// has, which is a weighted Lloyd relaxation. Every vertex stays within its surrounding
+    /// polygon and the mesh stays valid, the vertices on the edges of the grid aren't moved.
+    /// `get_target_pixel` returns the colour of the target at the given pixel, the target is
+    /// assumed to have the same dimensions as the image
+    pub fn place_vertices<U>(&mut self, get_target_pixel: &U, placement: VertexPlacement)
+    where
+        U: Fn(u16, u16) -> Colour,
+    {
+        place_vertices(self, get_target_pixel, placement);
+    }
+}
+
+/// See `GridImage::place_vertices`
+pub(super) fn place_vertices<G, U>(grid: &mut G, get_target_pixel: &U, placement: VertexPlacement)
+where
+    G: GridStorage + Triangulated,
+    U: Fn(u16, u16) -> Colour,
+{
+    let dimensions = grid.get_dimensions();
+    let detail = match placement {
+        VertexPlacement::Edges => sobel(get_target_pixel, dimensions),
+        VertexPlacement::Saliency => saliency(get_target_pixel, dimensions),
+    };
+    let (columns, rows) = grid.get_grid_size();
+    for _ in 0..PLACEMENT_ITERATIONS {
+        for vertical in 1..rows - 1 {
+            for horizontal in 1..columns - 1 {
+                let vert = GridVertex::new(horizontal, vertical);
+                if let Some(centroid) = detail_centroid(grid, &vert, &detail, dimensions) {
+                    move_towards(grid, &vert, centroid);
+                }
+            }
+        }
+    }
+}
+
+/// Gets the centroid of the pixels within the polygon surrounding a vertex, weighted by their
+/// detail, or None if the polygon has no detail
+fn detail_centroid<G: GridStorage>(
+    grid: &G,
+    vert: &GridVertex,
+    detail: &[f32],
+    dimensions: (u32, u32),
+) -> Option<(f64, f64)> {
+    let polygon = get_vertex_polygon(grid, vert);
+    let scale = (dimensions.0 as f64 / 65536.0, dimensions.1 as f64 / 65536.0);
+    // The pixels whose centres may lie within the polygon
+    let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    for (left, right) in neighbour_edges(grid, vert) {
+        for corner in [
+            grid.get_vert_position(&left),
+            grid.get_vert_position(&right),
+        ] {
+            let (x, y) = (corner.x as f64 * scale.0, corner.y as f64 * scale.1);
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+    }
+    let columns = (min.0.floor() as u32)..(max.0.ceil() as u32).min(dimensions.0);
+    let rows = (min.1.floor() as u32)..(max.1.ceil() as u32).min(dimensions.1);
+
+    let (mut total, mut x_total, mut y_total) = (0.0, 0.0, 0.0);
+    for row in rows {
+        for column in columns.clone() {
+            let weight = detail[row as usize * dimensions.0 as usize + column as usize] as f64;
+            let (x, y) = (
+                (column as f64 + 0.5) / scale.0,
+                (row as f64 + 0.5) / scale.1,
+            );
+            let point = Point::new(x.min(u16::MAX as f64) as u16, y.min(u16::MAX as f64) as u16);
+            if weight > 0.0 && polygon.contains(point) {
+                total += weight;
+                x_total += weight * x;
+                y_total += weight * y;
+            }
+        }
+    }
+    (total > 0.0).then_some((x_total / total, y_total / total))
+}
+
+/// Moves a vertex halfway towards `target`, halving the move until the vertex is within its
+/// polygon and its triangles are valid, the vertex is left where it is if no move is valid
+fn move_towards<G: GridStorage>(grid: &mut G, vert: &GridVertex, target: (f64, f64)) {
+    let origin = grid.get_vert_position(vert);
+    let polygon = get_vertex_polygon(grid, vert);
+    let mut step = 0.5;
+    for _ in 0..PLACEMENT_ATTEMPTS {
+        let towards = |from: u16, to: f64| {
+            (from as f64 + step * (to - from as f64))
+                .round()
+                .clamp(0.0, u16::MAX as f64) as u16
+        };
+        let point = Point::new(towards(origin.x, target.0), towards(origin.y, target.1));
+        if polygon.contains(point) {
+            grid.set_vert_position(vert, point);
+            if is_vertex_valid(grid, vert) {
+                return;
+            }
+            grid.set_vert_position(vert, origin);
+        }
+        step /= 2.0;
+    }
+}
+
+/// Gets the magnitude of the gradient of the target's luminance at every pixel, in row order,
+/// by the Sobel operator, pixels beyond the edges of the target repeat the edge pixels
+fn sobel<U>(get_target_pixel: &U, dimensions: (u32, u32)) -> Vec<f32>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    let (width, height) = (dimensions.0 as i64, dimensions.1 as i64);
+    let luminance: Vec<f32> = (0..width * height)
+        .map(|i| {
+            let c = get_target_pixel((i % width) as u16, (i / width) as u16);
+            0.299 * c.r as f32 + 0.587 * c.g as f32 + 0.114 * c.b as f32
+        })
+        .collect();
+    let at = |x: i64, y: i64| {
+        luminance[(y.clamp(0, height - 1) * width + x.clamp(0, width - 1)) as usize]
+    };
+    (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x - 1, y)
+                - at(x - 1, y + 1);
+            let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x, y - 1)
+                - at(x + 1, y - 1);
+            gx.hypot(gy)
+        })
+        .collect()
+}
+
+/// Gets the saliency of every pixel of the target, in row order, as the CIE76 distance of the
+/// pixel's colour, blurred slightly to remove noise, from the target's mean colour
+fn saliency<U>(get_target_pixel: &U, dimensions: (u32, u32)) -> Vec<f32>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    let lab: Vec<_> = box_blur(get_target_pixel, dimensions, 1)
+        .iter()
+        .map(Colour::to_lab)
+        .collect();
+    let count = lab.len() as f64;
+    let mut mean = lab[0];
+    mean.l = lab.iter().map(|c| c.l).sum::<f64>() / count;
+    mean.a = lab.iter().map(|c| c.a).sum::<f64>() / count;
+    mean.b = lab.iter().map(|c| c.b).sum::<f64>() / count;
+    lab.iter().map(|c| c.delta_e_cie76(&mean) as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A white target with a black disc of `radius` pixels at `centre`
+    fn disc(centre: (f64, f64), radius: f64) -> impl Fn(u16, u16) -> Colour {
+        move |x, y| {
+            let distance = (x as f64 + 0.5 - centre.0).hypot(y as f64 + 0.5 - centre.1);
+            if distance < radius {
+                Colour::new(0, 0, 0)
+            } else {
+                Colour::new(255, 255, 255)
+            }
+        }
+    }
+
+    #[test]
+    fn placement_keeps_the_edges_and_the_mesh_valid() {
+        let target = disc((40.0, 30.0), 15.0);
+        for placement in [VertexPlacement::Edges, VertexPlacement::Saliency] {
+            let mut image: GridImage<8, 6> = GridImage::new_uniform_with_dimensions(64, 48);
+            let original = *image.get_vertex_positions();
+            image.place_vertices(&target, placement);
+            let positions = image.get_vertex_positions();
+            assert!(positions != &original);
+            for (vertical, row) in positions.iter().enumerate() {
+                for (horizontal, position) in row.iter().enumerate() {
+                    if vertical == 0 || vertical == 5 || horizontal == 0 || horizontal == 7 {
+                        assert_eq!(*position, original[vertical][horizontal]);
+                    }
+                }
+            }
+            assert!(image.validate().is_empty());
+        }
+    }
+
+    #[test]
+    fn vertices_are_pulled_towards_a_salient_point() {
+        // The centre of the salient pixel in the domain, a 64x64 image has 1024 units per pixel
+        let point = (48.5 * 1024.0, 16.5 * 1024.0);
+        let distance = |p: &Point| (p.x as f64 - point.0).hypot(p.y as f64 - point.1);
+        let mut image: GridImage<6, 6> = GridImage::new_uniform_with_dimensions(64, 64);
+        let uniform = *image.get_vertex_positions();
+        image.place_vertices(&disc((48.5, 16.5), 1.0), VertexPlacement::Saliency);
+        let placed = image.get_vertex_positions();
+        // Every inner vertex moves closer, and the nearest ends within two pixels of the point
+        for (before, after) in uniform[1..5].iter().zip(&placed[1..5]) {
+            for (before, after) in before[1..5].iter().zip(&after[1..5]) {
+                assert!(distance(after) < distance(before));
+            }
+        }
+        let nearest = placed
+            .iter()
+            .flatten()
+            .map(distance)
+            .fold(f64::MAX, f64::min);
+        assert!(nearest < 2048.0);
+        assert!(image.validate().is_empty());
+    }
+}
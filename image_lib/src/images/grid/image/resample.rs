@@ -0,0 +1,214 @@
+use super::{Colour, GridImage, GridStorage, GridVertex, Point};
+use std::cmp::min;
+
+impl<const W: usize, const H: usize> GridImage<W, H> {
+    /// Converts the image to a grid of a different size, keeping its appearance. Every vertex of
+    /// the new grid is placed at the matching position of this grid's triangles, e.g. with
+    /// `GridImage<2W - 1, 2H - 1>` each cell is split into four at its edges' midpoints, and
+    /// every new triangle takes the colour of the triangle it lies in.
+    /// The appearance is kept exactly (up to rounding) when `W2 - 1` is a multiple of `W - 1`
+    /// and `H2 - 1` is a multiple of `H - 1`, otherwise some new triangles straddle an edge of
+    /// this grid and the appearance is only approximated
+    pub fn resample<const W2: usize, const H2: usize>(&self) -> GridImage<W2, H2> {
+        assert!(W2 >= 2 && H2 >= 2, "The grid must have at least one cell");
+        let mut image = GridImage {
+            vertex_positions: [[Point { x: 0, y: 0 }; W2]; H2],
+            colours: [[(Colour::transparent(), Colour::transparent()); W2]; H2],
+            vertex_colours: [[Colour::transparent(); W2]; H2],
+            flipped: [[false; W2]; H2],
+            palette_indices: [[(0, 0); W2]; H2],
+            palette: self.palette.clone(),
+            colour_mode: self.colour_mode,
+            dimensions: self.dimensions,
+        };
+        resample(self, &mut image);
+        image
+    }
+}
+
+/// Places every vertex of `target` at the matching position of `source`'s triangles and colours
+/// it from them, see `GridImage::resample`
+pub(super) fn resample<S: GridStorage, T: GridStorage>(source: &S, target: &mut T) {
+    let size = target.get_grid_size();
+    for vertical in 0..size.1 {
+        for horizontal in 0..size.0 {
+            let vert = GridVertex::new(horizontal, vertical);
+            let (_, weights, _) = sample(source, size, horizontal as i64, vertical as i64, 1);
+            target.set_vert_position(&vert, interpolate_position(source, weights));
+            target.set_vert_colour(&vert, interpolate_colour(source, weights));
+
+            // Each cell takes its diagonal from the cell that its centre lies in, so that a
+            // flipped diagonal is followed exactly
+            let (x, y) = (horizontal as i64, vertical as i64);
+            let (cell, _, _) = sample(source, size, 2 * x + 1, 2 * y + 1, 2);
+            let flipped = source.get_vert_flipped(&cell);
+            target.set_vert_flipped(&vert, flipped);
+
+            // Each triangle is coloured by the triangle that its centroid lies in, and takes its
+            // palette index
+            let (x, y) = (3 * x, 3 * y);
+            let (upper_centroid, lower_centroid) = if flipped {
+                ((x + 1, y + 1), (x + 2, y + 2))
+            } else {
+                ((x + 2, y + 1), (x + 1, y + 2))
+            };
+            let (upper_cell, _, upper) =
+                sample(source, size, upper_centroid.0, upper_centroid.1, 3);
+            let (lower_cell, _, lower) =
+                sample(source, size, lower_centroid.0, lower_centroid.1, 3);
+            let colour = |cell: GridVertex, upper: bool| {
+                let colours = source.get_vert_colours(&cell);
+                let indices = source.get_vert_palette_indices(&cell);
+                if upper {
+                    (colours.0, indices.0)
+                } else {
+                    (colours.1, indices.1)
+                }
+            };
+            let (upper_colour, upper_index) = colour(upper_cell, upper);
+            let (lower_colour, lower_index) = colour(lower_cell, lower);
+            target.set_vert_colours(&vert, (upper_colour, lower_colour));
+            target.set_vert_palette_indices(&vert, (upper_index, lower_index));
+        }
+    }
+}
+
+/// Finds the triangle of `grid` containing the position (x / scale, y / scale), in the vertex
+/// indices of a grid with `size` vertices in each axis. Returns the top left vertex of the cell
+/// the triangle is in, the vertices of the triangle with their barycentric weights, which sum to
+/// the weights' denominator, and whether the triangle is the upper triangle of its cell
+fn sample<S: GridStorage>(
+    grid: &S,
+    size: (usize, usize),
+    x: i64,
+    y: i64,
+    scale: i64,
+) -> (GridVertex, [(GridVertex, i64); 3], bool) {
+    let (width, height) = grid.get_grid_size();
+    let (denominator_x, denominator_y) = (scale * (size.0 - 1) as i64, scale * (size.1 - 1) as i64);
+    let (horizontal, a) = locate(x * (width - 1) as i64, denominator_x, width - 1);
+    let (vertical, b) = locate(y * (height - 1) as i64, denominator_y, height - 1);
+    // Gives both offsets within the cell the same denominator
+    let (a, b, total) = (
+        a * denominator_y,
+        b * denominator_x,
+        denominator_x * denominator_y,
+    );
+
+    let vert = GridVertex::new(horizontal, vertical);
+    if grid.get_vert_flipped(&vert) {
+        if a + b <= total {
+            (
+                vert,
+                [(vert, total - a - b), (vert.right(), a), (vert.down(), b)],
+                true,
+            )
+        } else {
+            (
+                vert,
+                [
+                    (vert.right(), total - b),
+                    (vert.down_right(), a + b - total),
+                    (vert.down(), total - a),
+                ],
+                false,
+            )
+        }
+    } else if a >= b {
+        (
+            vert,
+            [
+                (vert, total - a),
+                (vert.right(), a - b),
+                (vert.down_right(), b),
+            ],
+            true,
+        )
+    } else {
+        (
+            vert,
+            [
+                (vert, total - b),
+                (vert.down_right(), a),
+                (vert.down(), b - a),
+            ],
+            false,
+        )
+    }
+}
+
+/// Gets the position at the given barycentric weights, rounded to the nearest point
+fn interpolate_position<S: GridStorage>(grid: &S, weights: [(GridVertex, i64); 3]) -> Point {
+    let total: i64 = weights.iter().map(|(_, weight)| weight).sum();
+    let axis = |axis: fn(Point) -> u16| {
+        let sum: i64 = weights
+            .iter()
+            .map(|(vert, weight)| axis(grid.get_vert_position(vert)) as i64 * weight)
+            .sum();
+        ((sum + total / 2) / total) as u16
+    };
+    Point::new(axis(|p| p.x), axis(|p| p.y))
+}
+
+/// Gets the vertex colour at the given barycentric weights, rounded to the nearest colour
+fn interpolate_colour<S: GridStorage>(grid: &S, weights: [(GridVertex, i64); 3]) -> Colour {
+    let total: i64 = weights.iter().map(|(_, weight)| weight).sum();
+    let channel = |channel: fn(Colour) -> u8| {
+        let sum: i64 = weights
+            .iter()
+            .map(|(vert, weight)| channel(grid.get_vert_colour(vert)) as i64 * weight)
+            .sum();
+        ((sum + total / 2) / total) as u8
+    };
+    Colour::new_rgba(
+        channel(|c| c.r),
+        channel(|c| c.g),
+        channel(|c| c.b),
+        channel(|c| c.a),
+    )
+}
+
+/// Locates the position `numerator / denominator` along an axis of `cells` cells, as the cell it
+/// lies in and the remaining numerator. The end of the axis lies in the last cell
+fn locate(numerator: i64, denominator: i64, cells: usize) -> (usize, i64) {
+    let cell = min((numerator / denominator) as usize, cells - 1);
+    (cell, numerator - cell as i64 * denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colour::ColourSpace;
+    use crate::images::Triangulated;
+
+    fn pixels<T: Triangulated>(image: &T, size: (u32, u32)) -> Vec<Option<Colour>> {
+        let mut pixels = vec![None; size.0 as usize * size.1 as usize];
+        image.rasterize_pixels(size, (0, 0), |p, c| {
+            pixels[p.y as usize * size.0 as usize + p.x as usize] = Some(c);
+        });
+        pixels
+    }
+
+    #[test]
+    fn halving_every_cell_keeps_the_pixels() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        for _ in 0..1000 {
+            image.mutate_structure(&image.get_random_inner_vertex(), None);
+            image.flip_diagonal(&image.get_random_cell());
+            image.mutate_random_colour(ColourSpace::Rgb);
+        }
+        // Keeps every position even, so that the new midpoints need no rounding
+        for vertical in 0..6 {
+            for horizontal in 0..8 {
+                let vert = GridVertex::new(horizontal, vertical);
+                let position = image.get_vert_position(&vert);
+                image.set_vert_position(&vert, Point::new(position.x & !1, position.y & !1));
+            }
+        }
+
+        let resampled: GridImage<15, 11> = image.resample();
+        for size in [(64, 64), (100, 77)] {
+            assert!(pixels(&image, size) == pixels(&resampled, size));
+        }
+    }
+}
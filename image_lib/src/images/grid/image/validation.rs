@@ -0,0 +1,329 @@
+use super::mutation::flip_diagonal;
+use super::{cell_triangles, is_cell_valid, GridImage, GridStorage, GridVertex, Point, Tri};
+
+/// The number of times the vertices of invalid cells are smoothed by `repair`, before the grid is
+/// reset to a uniform grid
+const REPAIR_ITERATIONS: usize = 64;
+
+/// A defect of the mesh of a grid image, see `GridImage::validate`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MeshDefect {
+    /// A triangle of the cell whose top left vertex is `cell` has a negative orientation, `upper`
+    /// selects the triangle including the top edge of the cell
+    Inverted { cell: GridVertex, upper: bool },
+    /// A triangle of the cell whose top left vertex is `cell` has zero area
+    Degenerate { cell: GridVertex, upper: bool },
+    /// A triangle of the cell whose top left vertex is `cell` overlaps a triangle of `other`,
+    /// which is either the same cell or a neighbouring cell
+    Overlap { cell: GridVertex, other: GridVertex },
+    /// The vertex is outside the rectangle spanned by the top left and bottom right corners, or
+    /// is on an edge of the grid but not on the matching edge of that rectangle
+    OutOfRange { vert: GridVertex },
+}
+
+impl<const W: usize, const H: usize> GridImage<W, H> {
+    /// Finds every defect of the image's mesh, a valid mesh has none and covers the rectangle
+    /// spanned by its corners exactly once. Overlaps are only looked for between neighbouring
+    /// cells, which is where a fold of the mesh starts
+    pub fn validate(&self) -> Vec<MeshDefect> {
+        validate(self)
+    }
+
+    /// Resolves every defect of the image's mesh. Vertices are moved into range, then invalid
+    /// cells have their diagonal flipped or their vertices smoothed towards their neighbours,
+    /// if that fails the vertex positions are reset to a uniform grid. The colours are unchanged
+    pub fn repair(&mut self) {
+        repair(self);
+    }
+}
+
+/// See `GridImage::validate`
+pub(super) fn validate<G: GridStorage>(grid: &G) -> Vec<MeshDefect> {
+    let (width, height) = grid.get_grid_size();
+    let mut defects = Vec::new();
+
+    // Checks the vertices are in range
+    for vertical in 0..height {
+        for horizontal in 0..width {
+            let vert = GridVertex::new(horizontal, vertical);
+            if !is_in_range(grid, &vert) {
+                defects.push(MeshDefect::OutOfRange { vert });
+            }
+        }
+    }
+
+    for vertical in 0..(height - 1) {
+        for horizontal in 0..(width - 1) {
+            let cell = GridVertex::new(horizontal, vertical);
+            let triangles = get_cell_points(grid, &cell);
+
+            // Checks the orientation of the cell's triangles
+            for (triangle, upper) in triangles.iter().zip([true, false]) {
+                let orientation = Tri::new(triangle[0], triangle[1], triangle[2]).orientation();
+                if orientation < 0 {
+                    defects.push(MeshDefect::Inverted { cell, upper });
+                } else if orientation == 0 {
+                    defects.push(MeshDefect::Degenerate { cell, upper });
+                }
+            }
+
+            // Checks the cell against itself and the neighbouring cells right of and below it, so
+            // that each pair of neighbouring cells is only checked once
+            if is_overlapping(&triangles[0], &triangles[1]) {
+                defects.push(MeshDefect::Overlap { cell, other: cell });
+            }
+            let mut others = vec![cell.down()];
+            if horizontal > 0 {
+                others.push(cell.down_left());
+            }
+            if horizontal < width - 2 {
+                others.extend([cell.right(), cell.down_right()]);
+            }
+            for other in others {
+                if other.vertical >= height - 1 {
+                    continue;
+                }
+                let other_triangles = get_cell_points(grid, &other);
+                let overlapping = triangles.iter().any(|triangle| {
+                    other_triangles
+                        .iter()
+                        .any(|other_triangle| is_overlapping(triangle, other_triangle))
+                });
+                if overlapping {
+                    defects.push(MeshDefect::Overlap { cell, other });
+                }
+            }
+        }
+    }
+    defects
+}
+
+/// See `GridImage::repair`
+pub(super) fn repair<G: GridStorage>(grid: &mut G) {
+    let (width, height) = grid.get_grid_size();
+    let (top_left, bottom_right) = (
+        grid.get_vert_position(&GridVertex::new(0, 0)),
+        grid.get_vert_position(&GridVertex::new(width - 1, height - 1)),
+    );
+    if top_left.x >= bottom_right.x || top_left.y >= bottom_right.y {
+        reset_positions(grid, (top_left, bottom_right));
+        return;
+    }
+
+    // Moves the vertices into range, the vertices on each edge are spread evenly along it if
+    // they are out of order, as the cells along the edge would otherwise be inverted
+    for vertical in 0..height {
+        for horizontal in 0..width {
+            let vert = GridVertex::new(horizontal, vertical);
+            let position = grid.get_vert_position(&vert);
+            let (x, y) = get_range(grid, &vert);
+            grid.set_vert_position(
+                &vert,
+                Point::new(position.x.clamp(x.0, x.1), position.y.clamp(y.0, y.1)),
+            );
+        }
+    }
+    for edge in get_edges((width, height)) {
+        let ordered = edge.windows(2).all(|pair| {
+            let (first, second) = (
+                grid.get_vert_position(&pair[0]),
+                grid.get_vert_position(&pair[1]),
+            );
+            (first.x as u32 + first.y as u32) < (second.x as u32 + second.y as u32)
+        });
+        if !ordered {
+            let (first, last) = (
+                grid.get_vert_position(&edge[0]),
+                grid.get_vert_position(&edge[edge.len() - 1]),
+            );
+            for (i, vert) in edge.iter().enumerate() {
+                grid.set_vert_position(vert, interpolate(first, last, i, edge.len() - 1));
+            }
+        }
+    }
+
+    // Flipping the diagonal fixes cells which are convex, otherwise the inner vertices of each
+    // invalid cell are moved to the mean of their neighbours, which untangles folds of the mesh
+    for _ in 0..REPAIR_ITERATIONS {
+        let invalid = get_invalid_cells(grid);
+        if invalid.is_empty() {
+            break;
+        }
+        for cell in invalid {
+            if flip_diagonal(grid, &cell) {
+                continue;
+            }
+            for vert in [cell, cell.right(), cell.down_right(), cell.down()] {
+                if vert.horizontal == 0
+                    || vert.vertical == 0
+                    || vert.horizontal == width - 1
+                    || vert.vertical == height - 1
+                {
+                    continue;
+                }
+                let neighbours = [vert.up(), vert.right(), vert.down(), vert.left()]
+                    .map(|neighbour| grid.get_vert_position(&neighbour));
+                let mean = |axis: fn(&Point) -> u16| {
+                    (neighbours.iter().map(|p| axis(p) as u32).sum::<u32>() / 4) as u16
+                };
+                grid.set_vert_position(&vert, Point::new(mean(|p| p.x), mean(|p| p.y)));
+            }
+        }
+    }
+
+    // A uniform grid is always valid
+    if !validate(grid).is_empty() {
+        reset_positions(grid, (top_left, bottom_right));
+    }
+    debug_assert!(validate(grid).is_empty(), "The repaired mesh is invalid");
+}
+
+/// Gets the points of the two triangles of the cell whose top left vertex is `cell`
+fn get_cell_points<G: GridStorage>(grid: &G, cell: &GridVertex) -> [[Point; 3]; 2] {
+    cell_triangles(grid, cell).map(|triangle| triangle.map(|vert| grid.get_vert_position(&vert)))
+}
+
+/// Gets the top left vertex of every cell with an inverted or degenerate triangle
+fn get_invalid_cells<G: GridStorage>(grid: &G) -> Vec<GridVertex> {
+    let (width, height) = grid.get_grid_size();
+    (0..(height - 1))
+        .flat_map(|vertical| (0..(width - 1)).map(move |horizontal| (horizontal, vertical)))
+        .map(|(horizontal, vertical)| GridVertex::new(horizontal, vertical))
+        .filter(|cell| !is_cell_valid(grid, cell))
+        .collect()
+}
+
+/// Whether the interiors of the triangles intersect, degenerate triangles have no interior.
+/// By the separating axis theorem, the triangles don't intersect if one of their edges separates
+/// them
+fn is_overlapping(first: &[Point; 3], second: &[Point; 3]) -> bool {
+    let separates = |triangle: &[Point; 3], other: &[Point; 3]| {
+        (0..3).any(|i| {
+            let (from, to, opposite) = (triangle[i], triangle[(i + 1) % 3], triangle[(i + 2) % 3]);
+            let side = |point: Point| Tri::new(from, to, point).orientation().signum();
+            let inside = side(opposite);
+            other.iter().all(|point| side(*point) != inside)
+        })
+    };
+    let is_degenerate = |t: &[Point; 3]| Tri::new(t[0], t[1], t[2]).orientation() == 0;
+    !is_degenerate(first)
+        && !is_degenerate(second)
+        && !separates(first, second)
+        && !separates(second, first)
+}
+
+/// Whether the vertex is within its range, see `get_range`
+fn is_in_range<G: GridStorage>(grid: &G, vert: &GridVertex) -> bool {
+    let position = grid.get_vert_position(vert);
+    let (x, y) = get_range(grid, vert);
+    (x.0..=x.1).contains(&position.x) && (y.0..=y.1).contains(&position.y)
+}
+
+/// Gets the inclusive range of each axis that a vertex's position must be within, the rectangle
+/// spanned by the top left and bottom right corners, or one of its edges if the vertex is on
+/// the matching edge of the grid
+fn get_range<G: GridStorage>(grid: &G, vert: &GridVertex) -> ((u16, u16), (u16, u16)) {
+    let (width, height) = grid.get_grid_size();
+    let (top_left, bottom_right) = (
+        grid.get_vert_position(&GridVertex::new(0, 0)),
+        grid.get_vert_position(&GridVertex::new(width - 1, height - 1)),
+    );
+    let range = |index: usize, size: usize, start: u16, end: u16| match index {
+        0 => (start, start),
+        _ if index == size - 1 => (end, end),
+        _ => (start, end),
+    };
+    (
+        range(vert.horizontal, width, top_left.x, bottom_right.x),
+        range(vert.vertical, height, top_left.y, bottom_right.y),
+    )
+}
+
+/// Gets the vertices along each edge of the grid, in order from the top or left corner
+fn get_edges(size: (usize, usize)) -> [Vec<GridVertex>; 4] {
+    let (width, height) = size;
+    [
+        (0..width).map(|h| GridVertex::new(h, 0)).collect(),
+        (0..width).map(|h| GridVertex::new(h, height - 1)).collect(),
+        (0..height).map(|v| GridVertex::new(0, v)).collect(),
+        (0..height).map(|v| GridVertex::new(width - 1, v)).collect(),
+    ]
+}
+
+/// Gets the point `i / count` of the way from `first` to `last`
+fn interpolate(first: Point, last: Point, i: usize, count: usize) -> Point {
+    let axis = |first: u16, last: u16| {
+        (first as i64 + (last as i64 - first as i64) * i as i64 / count as i64) as u16
+    };
+    Point::new(axis(first.x, last.x), axis(first.y, last.y))
+}
+
+/// Places the vertices on a uniform grid spanning the rectangle between the corners
+fn reset_positions<G: GridStorage>(grid: &mut G, corners: (Point, Point)) {
+    let (width, height) = grid.get_grid_size();
+    let (top_left, bottom_right) = corners;
+    // A rectangle too small to separate every vertex is replaced by the whole image
+    let spans = |start: u16, end: u16, count: usize| end as usize >= start as usize + count - 1;
+    let (top_left, bottom_right) =
+        if spans(top_left.x, bottom_right.x, width) && spans(top_left.y, bottom_right.y, height) {
+            (top_left, bottom_right)
+        } else {
+            (Point::new(0, 0), Point::new(u16::MAX, u16::MAX))
+        };
+    for vertical in 0..height {
+        for horizontal in 0..width {
+            let x = interpolate(top_left, bottom_right, horizontal, width - 1).x;
+            let y = interpolate(top_left, bottom_right, vertical, height - 1).y;
+            grid.set_vert_position(&GridVertex::new(horizontal, vertical), Point::new(x, y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_and_mutated_meshes_are_valid() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        assert!(image.validate().is_empty());
+        for _ in 0..1000 {
+            image.mutate_structure(&image.get_random_inner_vertex(), None);
+            image.flip_diagonal(&image.get_random_cell());
+        }
+        assert!(image.validate().is_empty());
+    }
+
+    #[test]
+    fn folded_meshes_are_found_and_repaired() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        // Moves an inner vertex past its right neighbour, which folds the mesh
+        let vert = GridVertex::new(3, 3);
+        let right = image.get_vert_position(&vert.right());
+        image.set_vert_position(&vert, Point::new(right.x + 1000, right.y));
+        let defects = image.validate();
+        assert!(defects.contains(&MeshDefect::Inverted {
+            cell: vert,
+            upper: true
+        }));
+        assert!(defects
+            .iter()
+            .any(|defect| matches!(defect, MeshDefect::Overlap { .. })));
+
+        image.repair();
+        assert!(image.validate().is_empty());
+    }
+
+    #[test]
+    fn out_of_range_vertices_are_found_and_repaired() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        // Moves a vertex of the top edge off the edge
+        let vert = GridVertex::new(2, 0);
+        let position = image.get_vert_position(&vert);
+        image.set_vert_position(&vert, Point::new(position.x, 100));
+        assert_eq!(image.validate(), vec![MeshDefect::OutOfRange { vert }]);
+
+        image.repair();
+        assert!(image.validate().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use super::{Colour, GridImage, Point};
+use crate::images::{decode_dimensions, ColourMode};
+use genetic_algorithm_lib::workers::{Decode, Encode};
+
+/// A grid image is encoded as its grid size (W, H), followed by its vertex positions, its cell
+/// colours, its vertex colours, its cells' diagonal flips and its cells' palette indices, all in
+/// row order, then its palette, its colour mode (0 for flat, 1 for Gouraud, 2 for palette) and
+/// finally its real dimensions
+impl<const W: usize, const H: usize> Encode for GridImage<W, H> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (W as u32, H as u32).encode(out);
+        self.vertex_positions.encode(out);
+        self.colours.encode(out);
+        self.vertex_colours.encode(out);
+        self.flipped.encode(out);
+        self.palette_indices.encode(out);
+        self.palette.encode(out);
+        (self.colour_mode as u8).encode(out);
+        self.dimensions.encode(out);
+    }
+}
+
+impl<const W: usize, const H: usize> Decode for GridImage<W, H> {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        // Images of a different size can't be decoded into this one
+        if <(u32, u32)>::decode(input)? != (W as u32, H as u32) {
+            return None;
+        }
+        let vertex_positions = <[[Point; W]; H]>::decode(input)?;
+        let colours = <[[(Colour, Colour); W]; H]>::decode(input)?;
+        let vertex_colours = <[[Colour; W]; H]>::decode(input)?;
+        let flipped = <[[bool; W]; H]>::decode(input)?;
+        let palette_indices = <[[(u8, u8); W]; H]>::decode(input)?;
+        let palette = Vec::<Colour>::decode(input)?;
+        // Every index must be within the palette
+        let in_palette = |index: u8| (index as usize) < palette.len();
+        if palette.len() > 256
+            || !palette_indices
+                .iter()
+                .flatten()
+                .all(|&(upper, lower)| in_palette(upper) && in_palette(lower))
+        {
+            return None;
+        }
+        let colour_mode = match u8::decode(input)? {
+            0 => ColourMode::Flat,
+            1 => ColourMode::Gouraud,
+            2 => ColourMode::Palette,
+            _ => return None,
+        };
+        let dimensions = decode_dimensions(input)?;
+        Some(GridImage {
+            vertex_positions,
+            colours,
+            vertex_colours,
+            flipped,
+            palette_indices,
+            palette,
+            colour_mode,
+            dimensions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::grid::DynGridImage;
+
+    #[test]
+    fn palette_images_round_trip() {
+        let mut image: GridImage<8, 6> = GridImage::new_uniform();
+        image.set_palette(
+            (0..40)
+                .map(|i| Colour::new(i * 6, 255 - i * 6, 100))
+                .collect(),
+        );
+        image.set_colour_mode(ColourMode::Palette);
+        for _ in 0..1000 {
+            image.mutate_palette_index(&image.get_random_cell(), rand::random());
+        }
+        let mut genome = Vec::new();
+        image.encode(&mut genome);
+
+        let decoded = GridImage::<8, 6>::decode(&mut genome.as_slice()).unwrap();
+        assert_eq!(decoded.get_palette_indices(), image.get_palette_indices());
+        assert_eq!(decoded.get_palette(), image.get_palette());
+        assert!(decoded == image);
+
+        // A DynGridImage decodes the same genome and encodes it unchanged
+        let dynamic = DynGridImage::decode(&mut genome.as_slice()).unwrap();
+        let mut encoded = Vec::new();
+        dynamic.encode(&mut encoded);
+        assert_eq!(encoded, genome);
+    }
+
+    #[test]
+    fn images_without_an_area_are_rejected() {
+        for dimensions in [(0, 480), (640, 0), (0, 0)] {
+            let mut image: GridImage<4, 4> = GridImage::new_uniform();
+            image.dimensions = dimensions;
+            let mut genome = Vec::new();
+            image.encode(&mut genome);
+            assert!(GridImage::<4, 4>::decode(&mut genome.as_slice()).is_none());
+            assert!(DynGridImage::decode(&mut genome.as_slice()).is_none());
+        }
+    }
+}
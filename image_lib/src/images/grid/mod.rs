@@ -8,7 +8,12 @@ use polygon::Polygon;
 
 mod image;
 pub use image::{
-    AxisResolution, BreedMetadata, FitnessMetadata, GAImageMember, GridImage, Resolution,
+    CoevolvedGrid, ColourInitialisation, ColourSpecies, DynGridImage, DynGridMember, GAImageMember,
+    GeometrySpecies, GridImage, MeshDefect, VertexPlacement,
 };
 
 pub use crate::colour::Colour;
+pub use crate::images::{
+    AxisResolution, BreedMetadata, ColourMode, FitnessMetadata, Resolution, StructureMutation,
+    Triangulated,
+};
@@ -2,26 +2,57 @@ use super::Point;
 use super::Tri;
 use rand::Rng;
 
-/// E = number of edges in the polygon
-pub struct Polygon<const E: usize> {
+/// A polygon formed of triangles which all share the root
+pub struct Polygon {
     // The root of the polygon, (all edges are a part of a tri formed with the root)
     root: Point,
-    edges: [(Point, Point); E],
+    edges: Vec<(Point, Point)>,
 }
 
-impl<const E: usize> Polygon<E> {
-    pub fn new(root: Point, edges: [(Point, Point); E]) -> Polygon<E> {
+impl Polygon {
+    pub fn new(root: Point, edges: Vec<(Point, Point)>) -> Polygon {
         Polygon { root, edges }
     }
 
+    /// Gets the area of the polygon, the sum of the areas of its tris
+    pub fn get_area(&self) -> i64 {
+        self.edges
+            .iter()
+            .map(|(left, right)| Tri::new(self.root, *left, *right).orientation().abs() / 2)
+            .sum()
+    }
+
+    /// Whether the point is within the polygon, including its boundary
+    pub fn contains(&self, point: Point) -> bool {
+        // The point is within one of the tris if it is on the same side of each of the tri's
+        // edges as the tri's third vertex
+        self.edges.iter().any(|(left, right)| {
+            let sign = Tri::new(self.root, *left, *right).orientation().signum();
+            sign != 0
+                && [
+                    Tri::new(self.root, *left, point),
+                    Tri::new(*left, *right, point),
+                    Tri::new(*right, self.root, point),
+                ]
+                .iter()
+                .all(|tri| tri.orientation().signum() != -sign)
+        })
+    }
+
     /// Generates a random point within the polygon
     pub fn get_random_point(&self) -> Point {
+        // The goal is to pick one of the triangles making up the polygon
+        // (randomly, relative to area), and then within that triangle, pick a random point,
+        // respective to https://mathworld.wolfram.com/TrianglePointPicking.html
+
         // Generates the areas of every tri that makes up the polygon
-        let areas: [i32; E] = self
+        let areas: Vec<i64> = self
             .edges
-            .map(|(left, right)| Tri::new(self.root, left, right).area().abs());
+            .iter()
+            .map(|(left, right)| Tri::new(self.root, *left, *right).area())
+            .collect();
         // Finds the total of the areas
-        let total_area: i32 = areas.iter().sum();
+        let total_area: i64 = areas.iter().sum();
 
         if total_area == 0 {
             self.root
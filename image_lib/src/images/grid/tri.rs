@@ -1,4 +1,4 @@
-use crate::point::Point;
+use crate::point::{first_pixel_from, FixedPoint, Point, FIXED_POINT_ONE};
 use core::cmp;
 
 pub struct Tri {
@@ -16,92 +16,124 @@ impl Tri {
         }
     }
 
-    pub fn area(&self) -> i32 {
-        // TODO: Fix Mult Overflow
-        // Shoelace formula for triangle area
-        (self.first.x as i32 * (self.second.y as i32 - self.third.y as i32)
-            + self.second.x as i32 * (self.third.y as i32 - self.first.y as i32)
-            + self.third.x as i32 * (self.first.y as i32 - self.second.y as i32))
-            .abs()
-            / 2
+    /// The area of the tri, which is rounded down when it isn't whole
+    pub fn area(&self) -> i64 {
+        self.orientation().abs() / 2
     }
 
-    fn draw_triangle<T>(&self, mut on_point: T)
+    /// Twice the signed area of the tri, with y pointing down this is positive when the
+    /// vertices are in clockwise order on screen, (the order used by the grid's triangles),
+    /// negative if the tri is inverted and 0 if it is degenerate
+    pub fn orientation(&self) -> i64 {
+        (self.second.x as i64 - self.first.x as i64) * (self.third.y as i64 - self.first.y as i64)
+            - (self.second.y as i64 - self.first.y as i64)
+                * (self.third.x as i64 - self.first.x as i64)
+    }
+
+    /// Rasterizes the tri to a raster of `count` pixels in each axis, each pixel is sampled at
+    /// its fixed point position (see `FixedPoint`), so the vertices aren't snapped to the
+    /// raster's pixels. A pixel is drawn if its sample is within the tri, or on its top or left
+    /// edges (the top-left fill rule), so that tris which share an edge never both draw the
+    /// pixels on it. Samples on an edge along the right or bottom of the domain (at `u16::MAX`)
+    /// are drawn too, as no tri lies beyond them. Degenerate tris draw nothing
+    pub fn rasterize<T>(&self, count: (u32, u32), on_point: T)
     where
         T: FnMut(Point),
     {
-        // First sorts the verticies by height
-        let mut sorted = [self.first, self.second, self.third];
-        sorted.sort_by(|l, r| r.y.cmp(&l.y));
-        let v1 = sorted[0];
-        let v2 = sorted[1];
-        let v3 = sorted[2];
-
-        // If v1.y = v3.y then we know that the area of the tri must be 0
-        if v1.y == v3.y {
-            return;
-        } else {
-            let v1 = (v1.x as i64, v1.y as i64);
-            let v2 = (v2.x as i64, v2.y as i64);
-            let v3 = (v3.x as i64, v3.y as i64);
-            for y in v3.1..v1.1 {
-                // Gets v1 -> v3 (as v1 is highest and v3 is lowest) x
-                let long_x = v1.0 + ((v3.0 - v1.0) * (y - v1.1)) / (v3.1 - v1.1);
-
-                // If v1.x == v2.x then it is assumed that the tri containing v1 & v2 has zero area
-                if v1.1 == v2.1 && v2.1 == y {
-                } else if v2.1 == v3.1 && v2.1 == y { // Likewise for the v2 & v3 tri
-                } else {
-                    // Decides which short side we are getting x for
-                    let mut short_x = 0;
-                    if y > v2.1 {
-                        short_x = v1.0 + ((y - v1.1) * (v2.0 - v1.0)) / (v2.1 - v1.1);
-                    } else {
-                        short_x = v2.0 + ((y - v2.1) * (v3.0 - v2.0)) / (v3.1 - v2.1);
-                    }
-                    for x in if short_x < long_x {
-                        short_x..long_x
-                    } else {
-                        long_x..short_x
-                    } {
-                        on_point(Point {
-                            x: x as u16,
-                            y: y as u16,
-                        });
-                    }
-                }
-            }
-        }
+        self.rasterize_with_offset(count, (0, 0), on_point);
     }
 
-    /// Rasterizes the tri, the draw_{x}_{y} are booleans dictating whether the raster
-    /// should include the line between x and y
-    pub fn rasterize<T>(
-        &self,
-        draw_first_second: bool,
-        draw_second_third: bool,
-        draw_first_third: bool,
-        mut on_point: T,
-    ) where
+    /// Rasterizes the tri in the same way as `rasterize`, but the first pixel of each axis is
+    /// sampled at `offset` in the domain rather than at 0
+    pub fn rasterize_with_offset<T>(&self, count: (u32, u32), offset: (u16, u16), mut on_point: T)
+    where
         T: FnMut(Point),
     {
-        // TODO: This is terrible... fix it!!!
-        let min_x = cmp::min(cmp::min(self.first.x, self.second.x), self.third.x);
-        let min_y = cmp::min(cmp::min(self.first.y, self.second.y), self.third.y);
-        let max_x = cmp::max(cmp::max(self.first.x, self.second.x), self.third.x);
-        let max_y = cmp::max(cmp::max(self.first.y, self.second.y), self.third.y);
+        let [first, mut second, mut third] =
+            [self.first, self.second, self.third].map(|p| p.to_fixed(count, offset));
+        // Orders the vertices clockwise on screen, so the inside of every edge is positive
+        match FixedPoint::orientation(first, second, third).signum() {
+            0 => return,
+            -1 => std::mem::swap(&mut second, &mut third),
+            _ => {}
+        }
+        let edges = [(first, second), (second, third), (third, first)];
 
-        for x in min_x..=max_x {
-            for y in min_y..=max_y {
-                // If the point is in the tri, then rasterize it
-                if self.area()
-                    == Tri::new(self.first, self.second, Point { x, y }).area()
-                        + Tri::new(self.second, self.third, Point { x, y }).area()
-                        + Tri::new(self.first, self.third, Point { x, y }).area()
-                {
-                    on_point(Point { x, y });
+        // The pixels whose samples lie within the bounding box
+        let min_x = cmp::min(cmp::min(first.x, second.x), third.x);
+        let min_y = cmp::min(cmp::min(first.y, second.y), third.y);
+        let max_x = cmp::max(cmp::max(first.x, second.x), third.x);
+        let max_y = cmp::max(cmp::max(first.y, second.y), third.y);
+        let start = FixedPoint::from_pixel(
+            cmp::max(first_pixel_from(min_x), 0),
+            cmp::max(first_pixel_from(min_y), 0),
+        );
+        let end_x = cmp::min(max_x.div_euclid(FIXED_POINT_ONE) + 1, count.0 as i64);
+        let end_y = cmp::min(max_y.div_euclid(FIXED_POINT_ONE) + 1, count.1 as i64);
+
+        // Each edge function is linear, so it is evaluated once at the start of the box and
+        // then stepped by a constant amount for each row down
+        let values = edges.map(|(from, to)| FixedPoint::orientation(from, to, start));
+        let steps = edges.map(|(from, to)| {
+            (
+                (from.y - to.y) as i128 * FIXED_POINT_ONE as i128,
+                (to.x - from.x) as i128 * FIXED_POINT_ONE as i128,
+            )
+        });
+        // A sample on an edge is only inside the tri if the edge is a top edge (horizontal,
+        // with the inside below it), a left edge (going up the screen) or on the right or bottom
+        // of the domain, a bias of -1 on the other edges excludes the samples on them
+        let (right, bottom) = (
+            (u16::MAX - offset.0) as i64 * count.0 as i64,
+            (u16::MAX - offset.1) as i64 * count.1 as i64,
+        );
+        let biases = edges.map(|(from, to)| {
+            let is_top = from.y == to.y && from.x < to.x;
+            let is_left = to.y < from.y;
+            let is_boundary =
+                (from.x == right && to.x == right) || (from.y == bottom && to.y == bottom);
+            if is_top || is_left || is_boundary {
+                0
+            } else {
+                -1
+            }
+        });
+
+        // Each row's pixels within every edge form a span, as the edge functions are linear in x,
+        // so the span is found from the edges rather than by testing every pixel of the box
+        let first_x = (start.x / FIXED_POINT_ONE) as i128;
+        let mut row_values = values;
+        for y in start.y / FIXED_POINT_ONE..end_y {
+            let (mut span_start, mut span_end) = (first_x, end_x as i128);
+            for i in 0..3 {
+                // The k'th pixel of the row is within the edge when value + k * step >= 0
+                let (value, step) = (row_values[i] + biases[i], steps[i].0);
+                if step > 0 {
+                    span_start = span_start.max(first_x - floor_div(value, step));
+                } else if step < 0 {
+                    span_end = span_end.min(first_x + floor_div(value, -step) + 1);
+                } else if value < 0 {
+                    span_end = span_start;
                 }
             }
+            for x in span_start as i64..span_end as i64 {
+                on_point(Point {
+                    x: x as u16,
+                    y: y as u16,
+                });
+            }
+            for i in 0..3 {
+                row_values[i] += steps[i].1;
+            }
         }
     }
 }
+
+/// Divides `value` by the positive `divisor`, rounding down. The values of large rasters need
+/// i128, but the much quicker i64 division is used when they fit
+fn floor_div(value: i128, divisor: i128) -> i128 {
+    match (i64::try_from(value), i64::try_from(divisor)) {
+        (Ok(value), Ok(divisor)) => value.div_euclid(divisor) as i128,
+        _ => value.div_euclid(divisor),
+    }
+}
@@ -0,0 +1,464 @@
+use crate::colour::{Colour, ColourDistance, ColourSpace};
+use crate::images::grid::MeshDefect;
+use crate::images::{AxisResolution, Resolution, Triangulated};
+use genetic_algorithm_lib::workers::{Decode, Encode};
+use genetic_algorithm_lib::{Constrained, Lineage, Member, Refine, Traceable};
+use rand::random;
+use std::str::FromStr;
+
+#[derive(Clone)]
+pub struct FitnessMetadata {
+    /// The offset to perform rasterisation at
+    offset: (u16, u16),
+    /// The resolution of the fitness calculation, the image's longer axis is rasterized at this
+    /// resolution and its shorter axis in proportion to the image's aspect ratio
+    resolution: Resolution,
+    /// How the difference between the image and the target is measured
+    distance: ColourDistance,
+}
+
+impl FitnessMetadata {
+    /// Creates metadata which measures the RGB distance between the image and the target
+    pub fn new(offset: (u16, u16), resolution: Resolution) -> Self {
+        FitnessMetadata::new_with_distance(offset, resolution, ColourDistance::Rgb)
+    }
+
+    pub fn new_with_distance(
+        offset: (u16, u16),
+        resolution: Resolution,
+        distance: ColourDistance,
+    ) -> Self {
+        FitnessMetadata {
+            offset,
+            resolution,
+            distance,
+        }
+    }
+}
+
+/// The resolution is encoded as the number of pixels in each axis, and the distance as 0 for RGB,
+/// 1 for CIE76 and 2 for CIEDE2000
+impl Encode for FitnessMetadata {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.offset.encode(out);
+        self.resolution.get_pixel_count().encode(out);
+        (self.distance as u8).encode(out);
+    }
+}
+
+impl Decode for FitnessMetadata {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let offset = Decode::decode(input)?;
+        let (x_count, y_count) = Decode::decode(input)?;
+        let resolution = Resolution(
+            AxisResolution::from_pixel_count(x_count)?,
+            AxisResolution::from_pixel_count(y_count)?,
+        );
+        let distance = match u8::decode(input)? {
+            0 => ColourDistance::Rgb,
+            1 => ColourDistance::Cie76,
+            2 => ColourDistance::Ciede2000,
+            _ => return None,
+        };
+        Some(FitnessMetadata::new_with_distance(
+            offset, resolution, distance,
+        ))
+    }
+}
+
+pub struct BreedMetadata {
+    /// The mutation rate of the breeding process
+    mutation_rate: f32,
+    /// The colour space that colours are mutated in
+    mutation_space: ColourSpace,
+    /// How far vertices are moved by structure mutations
+    structure_mutation: StructureMutation,
+}
+
+impl BreedMetadata {
+    /// Creates metadata which mutates colours in RGB
+    pub fn new(mutation_rate: f32) -> Self {
+        BreedMetadata::new_with_space(mutation_rate, ColourSpace::Rgb)
+    }
+
+    /// Creates metadata which moves vertices anywhere within their surrounding polygon
+    pub fn new_with_space(mutation_rate: f32, mutation_space: ColourSpace) -> Self {
+        BreedMetadata::new_with_mutations(
+            mutation_rate,
+            mutation_space,
+            StructureMutation::Uniform(None),
+        )
+    }
+
+    pub fn new_with_mutations(
+        mutation_rate: f32,
+        mutation_space: ColourSpace,
+        structure_mutation: StructureMutation,
+    ) -> Self {
+        BreedMetadata {
+            mutation_rate,
+            mutation_space,
+            structure_mutation,
+        }
+    }
+
+    pub fn get_mutation_rate(&self) -> f32 {
+        self.mutation_rate
+    }
+
+    pub fn get_mutation_space(&self) -> ColourSpace {
+        self.mutation_space
+    }
+
+    pub fn get_structure_mutation(&self) -> StructureMutation {
+        self.structure_mutation
+    }
+}
+
+/// How a vertex is moved by a structure mutation, in either case the vertex stays within its
+/// surrounding polygon and none of its triangles are inverted
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StructureMutation {
+    /// The vertex is moved to a uniformly random point of its surrounding polygon, within the
+    /// given radius of its current position if there is one
+    Uniform(Option<u32>),
+    /// The vertex is moved by a normally distributed offset in each axis, with the given standard
+    /// deviation
+    Gaussian(f32),
+}
+
+/// Parses `uniform`, `radius:<radius>` or `gaussian:<sigma>`
+impl FromStr for StructureMutation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "uniform" {
+            return Ok(StructureMutation::Uniform(None));
+        }
+        if let Some(Ok(radius)) = s.strip_prefix("radius:").map(u32::from_str) {
+            return Ok(StructureMutation::Uniform(Some(radius)));
+        }
+        match s.strip_prefix("gaussian:").map(f32::from_str) {
+            Some(Ok(sigma)) if sigma > 0.0 => Ok(StructureMutation::Gaussian(sigma)),
+            _ => Err(format!("unknown structure mutation `{}`", s)),
+        }
+    }
+}
+
+/// Gets the number of samples taken in each axis of an image with the given dimensions, the
+/// longer axis takes its count from the resolution and the shorter axis is scaled down to keep
+/// the samples square
+fn sample_counts(resolution: Resolution, dimensions: (u32, u32)) -> (u32, u32) {
+    let (x_count, y_count) = resolution.get_pixel_count();
+    let (width, height) = (dimensions.0 as u64, dimensions.1 as u64);
+    if width >= height {
+        (
+            x_count,
+            ((x_count as u64 * height + width / 2) / width).max(1) as u32,
+        )
+    } else {
+        (
+            ((y_count as u64 * width + height / 2) / height).max(1) as u32,
+            y_count,
+        )
+    }
+}
+
+/// Calculates the fitness of an image against the target, this is the maximum feasible
+/// difference between the image and the target minus the actual difference.
+/// The target is assumed to have the same dimensions as the image. Colours are compared
+/// premultiplied, along with their alpha, so the colour of a transparent pixel is irrelevant,
+/// using the metadata's colour distance
+pub(crate) fn image_fitness<U, I>(
+    image: &I,
+    get_target_pixel: &U,
+    metadata: &FitnessMetadata,
+) -> u64
+where
+    U: Fn(u16, u16) -> Colour,
+    I: Triangulated,
+{
+    let (width, height) = image.get_dimensions();
+    let (x_count, y_count) = sample_counts(metadata.resolution, (width, height));
+    // Finds the target pixel that a sample lies in, the i'th sample is at the domain position
+    // offset + i * 65536 / count, which is scaled to the image's dimensions
+    let to_pixel = |sample: u16, offset: u16, count: u32, dimension: u32| {
+        let position = offset as u64 * count as u64 + sample as u64 * 65536;
+        (position * dimension as u64 / (count as u64 * 65536)).min(dimension as u64 - 1) as u16
+    };
+
+    // Calculates the absolute difference between the image and the target
+    let mut difference: u64 = 0;
+    image.rasterize_pixels((x_count, y_count), metadata.offset, |p, c| {
+        let p = (*get_target_pixel)(
+            to_pixel(p.x, metadata.offset.0, x_count, width),
+            to_pixel(p.y, metadata.offset.1, y_count, height),
+        );
+        // Each sample is expected once, images whose shapes overlap composite them while
+        // rasterizing, see `Overlaid`
+        difference += metadata.distance.difference(c, p);
+    });
+    // The fitness is the maximum feasible difference between member and target image - fitness
+    (ColourDistance::MAX_DIFFERENCE * x_count as u64 * y_count as u64).saturating_sub(difference)
+}
+
+/// An image that can be evolved by an `ImageMember`
+pub trait Breedable: Triangulated + Clone + PartialEq {
+    /// Breeds two images into a new image and mutates it as described by the metadata,
+    /// on_operator is called with the name of every operator that was applied
+    fn breed_traced<F>(left: &Self, right: &Self, metadata: &BreedMetadata, on_operator: F) -> Self
+    where
+        F: FnMut(&'static str);
+}
+
+/// An image with a mesh whose defects can be found and repaired, its member is constrained by
+/// the defects
+pub trait Meshed {
+    fn validate(&self) -> Vec<MeshDefect>;
+
+    fn repair(&mut self);
+}
+
+/// An image that can be changed by a small step at a time, its member is refined by local search
+pub trait Refinable {
+    /// Moves a random vertex by the structure mutation
+    fn mutate_random_vertex(&mut self, structure: StructureMutation);
+
+    /// Tweaks a random colour of the image in `space`
+    fn mutate_random_colour(&mut self, space: ColourSpace);
+}
+
+/// A member which evolves an image towards a target image
+pub struct ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    /// The image that this ImageMember encapsulates
+    image: I,
+    /// A function which returns the colour of the target image at the given pixel, the target
+    /// image is assumed to have the same dimensions as the image
+    get_target_pixel: &'a U,
+    /// The identity, parents and operators that created this member
+    lineage: Lineage,
+}
+
+impl<'a, U, I> ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    pub fn new(image: I, get_target_pixel: &'a U) -> Self {
+        ImageMember {
+            image,
+            get_target_pixel,
+            lineage: Lineage::new(),
+        }
+    }
+
+    pub fn get_image(&self) -> &I {
+        &self.image
+    }
+
+    /// Creates a child of this member with the given image, e.g. a converted copy of this
+    /// member's image, recording the operator that produced it
+    pub(crate) fn derive(&self, image: I, operator: &'static str) -> Self {
+        let mut lineage = Lineage::child(&[&self.lineage]);
+        lineage.record(operator);
+        ImageMember {
+            image,
+            get_target_pixel: self.get_target_pixel,
+            lineage,
+        }
+    }
+}
+
+/// Implemented by hand as the target function doesn't need to be Clone to share a reference to it
+impl<'a, U, I> Clone for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: Clone,
+{
+    fn clone(&self) -> Self {
+        ImageMember {
+            image: self.image.clone(),
+            get_target_pixel: self.get_target_pixel,
+            lineage: self.lineage.clone(),
+        }
+    }
+}
+
+/// Members are equal if their images are, the target is assumed to be shared
+impl<'a, U, I> PartialEq for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.image == other.image
+    }
+}
+
+impl<'a, U, I> Member for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: Breedable,
+{
+    type FitnessMetadata = FitnessMetadata;
+    type BreedMetadata = BreedMetadata;
+
+    fn fitness(&self, metadata: &Self::FitnessMetadata) -> u64 {
+        image_fitness(&self.image, self.get_target_pixel, metadata)
+    }
+
+    fn breed(left: &Self, right: &Self, metadata: &Self::BreedMetadata) -> Self {
+        let mut lineage = Lineage::child(&[&left.lineage, &right.lineage]);
+        let image = I::breed_traced(&left.image, &right.image, metadata, |o| lineage.record(o));
+        ImageMember {
+            image,
+            get_target_pixel: left.get_target_pixel,
+            lineage,
+        }
+    }
+}
+
+impl<'a, U, I> Traceable for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+{
+    fn lineage(&self) -> &Lineage {
+        &self.lineage
+    }
+}
+
+/// A member is infeasible if its mesh has any defects, each defect is a unit of violation, and
+/// it is repaired by repairing its mesh
+impl<'a, U, I> Constrained for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: Breedable + Meshed,
+{
+    fn violation(&self, _metadata: &Self::FitnessMetadata) -> u64 {
+        self.image.validate().len() as u64
+    }
+
+    fn repair(&mut self, _metadata: &Self::FitnessMetadata) {
+        self.image.repair();
+    }
+}
+
+/// Only the image is encoded, the worker evaluating the member supplies its own target
+impl<'a, U, I> Encode for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: Encode,
+{
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.image.encode(out);
+    }
+}
+
+/// Greedy hill climbing, each step either moves a random vertex by the structure mutation or
+/// tweaks a random colour, and the step is only kept if it improves the fitness
+impl<'a, U, I> Refine for ImageMember<'a, U, I>
+where
+    U: Fn(u16, u16) -> Colour,
+    I: Breedable + Refinable,
+{
+    fn refine(
+        &self,
+        metadata: &Self::FitnessMetadata,
+        breed_metadata: &Self::BreedMetadata,
+        budget: usize,
+    ) -> (Self, u64) {
+        let mut best = self.clone();
+        let mut best_fitness = best.fitness(metadata);
+        for _ in 0..budget {
+            let mut candidate = best.clone();
+            if random() {
+                candidate
+                    .image
+                    .mutate_random_vertex(breed_metadata.get_structure_mutation());
+                candidate.lineage.record("local_search_structure");
+            } else {
+                candidate
+                    .image
+                    .mutate_random_colour(breed_metadata.get_mutation_space());
+                candidate.lineage.record("local_search_colour");
+            }
+
+            let fitness = candidate.fitness(metadata);
+            if fitness > best_fitness {
+                best = candidate;
+                best_fitness = fitness;
+            }
+        }
+        (best, best_fitness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::grid::GridImage;
+    use genetic_algorithm_lib::{LocalSearch, Population};
+
+    fn target(x: u16, y: u16) -> Colour {
+        Colour::new_rgba((x * 4) as u8, (y * 4) as u8, 128, 255)
+    }
+
+    #[test]
+    fn samples_are_square_along_the_longer_axis() {
+        let resolution = Resolution(AxisResolution::Blocks64, AxisResolution::Blocks64);
+        assert_eq!(sample_counts(resolution, (640, 480)), (64, 48));
+        assert_eq!(sample_counts(resolution, (480, 640)), (48, 64));
+        assert_eq!(sample_counts(resolution, (100, 100)), (64, 64));
+        // The shorter axis rounds to the nearest count
+        assert_eq!(sample_counts(resolution, (640, 475)), (64, 48));
+        assert_eq!(sample_counts(resolution, (640, 485)), (64, 49));
+
+        // The longer axis takes its own count from the resolution
+        let uneven = Resolution(AxisResolution::Blocks128, AxisResolution::Blocks32);
+        assert_eq!(sample_counts(uneven, (640, 480)), (128, 96));
+        assert_eq!(sample_counts(uneven, (480, 640)), (24, 32));
+
+        // An extremely thin image still takes a sample across its shorter axis
+        assert_eq!(sample_counts(resolution, (1, 1000)), (1, 64));
+        assert_eq!(sample_counts(resolution, (10000, 1)), (64, 1));
+        assert_eq!(sample_counts(resolution, (1, 1)), (64, 64));
+    }
+
+    #[test]
+    fn refined_members_are_never_worse() {
+        let metadata = FitnessMetadata::new(
+            (0, 0),
+            Resolution(AxisResolution::Blocks32, AxisResolution::Blocks32),
+        );
+        let breed_metadata = BreedMetadata::new_with_space(0.5, ColourSpace::Lab);
+        let uniform = ImageMember::new(
+            GridImage::<6, 6>::new_uniform_with_dimensions(64, 64),
+            &target,
+        );
+        for _ in 0..10 {
+            let member = ImageMember::breed(&uniform, &uniform, &breed_metadata);
+            let (refined, fitness) = member.refine(&metadata, &breed_metadata, 20);
+            assert!(fitness >= member.fitness(&metadata));
+            assert_eq!(fitness, refined.fitness(&metadata));
+        }
+
+        // Lamarckian search keeps the refined image, Baldwinian search only its fitness
+        for mode in [LocalSearch::Lamarckian, LocalSearch::Baldwinian] {
+            let members = [0; 4].map(|_| ImageMember::breed(&uniform, &uniform, &breed_metadata));
+            let population = Population::new(members.clone());
+            let evaluated = population.run_memetic(&metadata, &breed_metadata, mode, 20);
+            for (member, fitness) in evaluated.members() {
+                let own = member.fitness(&metadata);
+                match mode {
+                    LocalSearch::Lamarckian => assert_eq!(*fitness, own),
+                    LocalSearch::Baldwinian => {
+                        assert!(*fitness >= own);
+                        assert!(members.contains(member));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1 +1,14 @@
+pub mod delaunay;
 pub mod grid;
+pub mod overlay;
+pub mod voronoi;
+
+mod rasters;
+pub(crate) use rasters::decode_dimensions;
+pub use rasters::{AxisResolution, ColourMode, Resolution, Triangulated};
+
+mod member;
+pub(crate) use member::image_fitness;
+pub use member::{
+    BreedMetadata, Breedable, FitnessMetadata, ImageMember, Meshed, Refinable, StructureMutation,
+};
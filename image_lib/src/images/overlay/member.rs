@@ -0,0 +1,41 @@
+use crate::images::grid::MeshDefect;
+use crate::images::overlay::{Overlaid, ShapeLayer};
+use crate::images::{BreedMetadata, Breedable, ImageMember, Meshed};
+
+/// A member which evolves a base image, e.g. a `GridImage` or a `DynGridImage`, with a layer of
+/// shapes over it
+pub type OverlaidMember<'a, U, I> = ImageMember<'a, U, Overlaid<I>>;
+
+/// The bases and the layers are bred separately, and the operators of both are recorded
+impl<I: Breedable> Breedable for Overlaid<I> {
+    fn breed_traced<F>(
+        left: &Self,
+        right: &Self,
+        metadata: &BreedMetadata,
+        mut on_operator: F,
+    ) -> Self
+    where
+        F: FnMut(&'static str),
+    {
+        let base = I::breed_traced(&left.base, &right.base, metadata, &mut on_operator);
+        let layer = ShapeLayer::breed_traced(
+            &left.layer,
+            &right.layer,
+            metadata.get_mutation_rate(),
+            metadata.get_mutation_space(),
+            on_operator,
+        );
+        Overlaid::new(base, layer)
+    }
+}
+
+/// The shapes are free, so only the mesh of the base is constrained
+impl<I: Meshed> Meshed for Overlaid<I> {
+    fn validate(&self) -> Vec<MeshDefect> {
+        self.base.validate()
+    }
+
+    fn repair(&mut self) {
+        self.base.repair();
+    }
+}
@@ -0,0 +1,199 @@
+/// A layer of free translucent shapes which is composited over a base image, so that details
+/// finer than the base's structure, such as eyes or text, can be expressed
+use crate::colour::Colour;
+use crate::images::Triangulated;
+use crate::point::Point;
+use rand::Rng;
+mod member;
+mod mutation;
+mod shape;
+mod wire;
+pub use member::OverlaidMember;
+pub use shape::Shape;
+
+/// A list of translucent shapes, the shapes are drawn in order so later shapes are on top
+#[derive(Clone, PartialEq)]
+pub struct ShapeLayer {
+    /// The geometry of every shape
+    shapes: Vec<Shape>,
+    /// The colour of every shape, which is usually translucent
+    colours: Vec<Colour>,
+}
+
+impl ShapeLayer {
+    /// Creates a layer of `count` random shapes with random translucent colours
+    pub fn new_random(count: usize) -> ShapeLayer {
+        ShapeLayer {
+            shapes: (0..count).map(|_| Shape::new_random()).collect(),
+            colours: (0..count).map(|_| random_colour()).collect(),
+        }
+    }
+
+    pub fn get_shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    pub fn get_colours(&self) -> &[Colour] {
+        &self.colours
+    }
+
+    /// Gets the triangles of every shape in drawing order, along with the shape's colour, the
+    /// shapes are scaled to an image of `dimensions` pixels. The triangles of a shape don't
+    /// overlap each other
+    pub fn get_triangles<F>(&self, dimensions: (u32, u32), mut on_triangle: F)
+    where
+        F: FnMut([Point; 3], Colour),
+    {
+        for (shape, colour) in self.shapes.iter().zip(&self.colours) {
+            shape.get_triangles(dimensions, |triangle| on_triangle(triangle, *colour));
+        }
+    }
+}
+
+/// An image drawn as its base with a layer of shapes composited over it
+#[derive(Clone, PartialEq)]
+pub struct Overlaid<I> {
+    base: I,
+    layer: ShapeLayer,
+}
+
+impl<I> Overlaid<I> {
+    pub fn new(base: I, layer: ShapeLayer) -> Overlaid<I> {
+        Overlaid { base, layer }
+    }
+
+    pub fn get_base(&self) -> &I {
+        &self.base
+    }
+
+    pub fn get_layer(&self) -> &ShapeLayer {
+        &self.layer
+    }
+}
+
+/// The triangles of the image are those of the base followed by those of every shape, in the
+/// order that they are drawn. As the shapes overlap the base, rasterizing composites each shape
+/// over the pixels of the base rather than reporting every triangle
+impl<I: Triangulated> Triangulated for Overlaid<I> {
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.base.get_dimensions()
+    }
+
+    fn get_shaded_triangles<F>(&self, mut on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]),
+    {
+        self.base.get_shaded_triangles(&mut on_triangle);
+        self.layer
+            .get_triangles(self.get_dimensions(), |[a, b, c], colour| {
+                on_triangle([(a, colour), (b, colour), (c, colour)])
+            });
+    }
+
+    /// Rasterizes the base, then blends the shapes over it in order, on_point is called once for
+    /// every pixel that the base covers, in row order
+    fn rasterize_pixels<F>(&self, size: (u32, u32), offset: (u16, u16), mut on_point: F)
+    where
+        F: FnMut(Point, Colour),
+    {
+        let mut pixels = vec![None; size.0 as usize * size.1 as usize];
+        let index = |p: Point| p.y as usize * size.0 as usize + p.x as usize;
+        self.base
+            .rasterize_pixels(size, offset, |p, c| pixels[index(p)] = Some(c));
+        let shapes = Shapes {
+            layer: &self.layer,
+            dimensions: self.get_dimensions(),
+        };
+        shapes.rasterize_pixels(size, offset, |p, c| {
+            if let Some(pixel) = &mut pixels[index(p)] {
+                *pixel = c.blend_over(*pixel);
+            }
+        });
+        for (i, pixel) in pixels.into_iter().enumerate() {
+            if let Some(colour) = pixel {
+                on_point(
+                    Point::new((i % size.0 as usize) as u16, (i / size.0 as usize) as u16),
+                    colour,
+                );
+            }
+        }
+    }
+}
+
+/// The shapes of a layer on their own, so that they can be rasterized with the default
+/// rasterizer
+struct Shapes<'a> {
+    layer: &'a ShapeLayer,
+    dimensions: (u32, u32),
+}
+
+impl<'a> Triangulated for Shapes<'a> {
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn get_shaded_triangles<F>(&self, mut on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]),
+    {
+        self.layer
+            .get_triangles(self.dimensions, |[a, b, c], colour| {
+                on_triangle([(a, colour), (b, colour), (c, colour)])
+            });
+    }
+}
+
+/// A random colour which is translucent enough that the shapes below it show through
+fn random_colour() -> Colour {
+    let mut thread_rng = rand::thread_rng();
+    Colour::new_rgba(
+        thread_rng.gen(),
+        thread_rng.gen(),
+        thread_rng.gen(),
+        thread_rng.gen_range(32..=128),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::grid::{ColourInitialisation, GridImage};
+
+    #[test]
+    fn shapes_are_composited_over_the_base_in_order() {
+        // The edges of a uniform 6x6 grid are at u16::MAX, so an opaque base covers every pixel
+        let blue = Colour::new(0, 0, 255);
+        let base: GridImage<6, 6> =
+            GridImage::new_uniform_from_target(64, 64, &|_, _| blue, ColourInitialisation::Mean);
+        let half = Shape::Triangle {
+            points: [
+                Point::new(0, 0),
+                Point::new(u16::MAX, 0),
+                Point::new(0, u16::MAX),
+            ],
+        };
+        let mut layer = ShapeLayer {
+            shapes: vec![half],
+            colours: vec![Colour::new_rgba(255, 0, 0, 128)],
+        };
+
+        let pixels = |layer: &ShapeLayer| {
+            let image = Overlaid::new(base.clone(), layer.clone());
+            let mut pixels = Vec::new();
+            image.rasterize_pixels((64, 64), (0, 0), |_, c| pixels.push(c));
+            pixels
+        };
+        // The top left pixel is under the shape, and the bottom right isn't
+        let blended = pixels(&layer);
+        assert_eq!(blended.len(), 64 * 64);
+        assert_eq!(blended[0], Colour::new_rgba(128, 0, 127, 255));
+        assert_eq!(blended[64 * 64 - 1], blue);
+
+        // A shape drawn later is blended over the earlier shapes
+        layer.shapes.push(half);
+        layer.colours.push(Colour::new_rgba(0, 255, 0, 64));
+        let blended = pixels(&layer);
+        assert_eq!(blended[0], Colour::new_rgba(96, 64, 95, 255));
+        assert_eq!(blended[64 * 64 - 1], blue);
+    }
+}
@@ -0,0 +1,214 @@
+use super::shape::offset_point;
+use super::{random_colour, Shape, ShapeLayer};
+use crate::colour::ColourSpace;
+use rand::{random, Rng};
+
+impl ShapeLayer {
+    /// Moves the shape at `index` by up to `radius` in each axis, without changing its form
+    pub fn move_shape(&mut self, index: usize, radius: u16) {
+        let shape = &mut self.shapes[index];
+        match shape {
+            Shape::Triangle { points } => {
+                // Every point is moved by the same amount as the first
+                let first = points[0];
+                let moved = offset_point(first, radius);
+                for point in points.iter_mut() {
+                    point.x = point.x.saturating_add(moved.x).saturating_sub(first.x);
+                    point.y = point.y.saturating_add(moved.y).saturating_sub(first.y);
+                }
+            }
+            Shape::Circle { centre, .. } | Shape::Ellipse { centre, .. } => {
+                *centre = offset_point(*centre, radius);
+            }
+        }
+    }
+
+    /// Changes the form of the shape at `index` by up to `radius`: a triangle has one of its
+    /// points moved, a circle is resized and an ellipse is resized or rotated
+    pub fn reshape_shape(&mut self, index: usize, radius: u16) {
+        let mut thread_rng = rand::thread_rng();
+        let mut resize = |value: u16| {
+            thread_rng.gen_range(value.saturating_sub(radius)..=value.saturating_add(radius))
+        };
+        match &mut self.shapes[index] {
+            Shape::Triangle { points } => {
+                let i = rand::thread_rng().gen_range(0..3);
+                points[i] = offset_point(points[i], radius);
+            }
+            Shape::Circle { radius: r, .. } => *r = resize(*r),
+            Shape::Ellipse {
+                radii, rotation, ..
+            } => match rand::thread_rng().gen_range(0..3) {
+                0 => radii.0 = resize(radii.0),
+                1 => radii.1 = resize(radii.1),
+                // Rotation wraps around, as a half turn leaves an ellipse unchanged
+                _ => {
+                    let turn = resize(u16::MAX / 2).wrapping_sub(u16::MAX / 2);
+                    *rotation = rotation.wrapping_add(turn);
+                }
+            },
+        }
+    }
+
+    /// Perturbs the colour of the shape at `index` in `space`, see `Colour::perturb`
+    pub fn recolour_shape(&mut self, index: usize, space: ColourSpace) {
+        self.colours[index] = self.colours[index].perturb(space);
+    }
+
+    /// Replaces the shape at `index` with a new random shape and colour
+    pub fn replace_shape(&mut self, index: usize) {
+        self.shapes[index] = Shape::new_random();
+        self.colours[index] = random_colour();
+    }
+
+    /// Swaps the drawing order of the shapes at `i` and `j`
+    pub fn swap_shapes(&mut self, i: usize, j: usize) {
+        self.shapes.swap(i, j);
+        self.colours.swap(i, j);
+    }
+
+    /// Gets the index of a random shape, the layer must have a shape
+    pub fn get_random_shape(&self) -> usize {
+        rand::thread_rng().gen_range(0..self.shapes.len())
+    }
+}
+
+/// A collection of functions to aid genetic mutation and breeding
+impl ShapeLayer {
+    /// Breeds the layers with a uniform crossover, the child's i'th shape is the i'th shape of
+    /// either parent, chosen at random, so the child has as many shapes as the longer parent.
+    /// Each shape of the child then has a `mutation_rate` chance of being moved, of being
+    /// reshaped and of its colour being mutated in `space`, and a quarter of that chance of being
+    /// replaced and of swapping its drawing order with another shape.
+    /// `on_operator` is called with the name of each operator that is applied to the child
+    pub fn breed_traced<F>(
+        left: &ShapeLayer,
+        right: &ShapeLayer,
+        mutation_rate: f32,
+        space: ColourSpace,
+        mut on_operator: F,
+    ) -> ShapeLayer
+    where
+        F: FnMut(&'static str),
+    {
+        on_operator("shape_crossover");
+        let mut layer = ShapeLayer {
+            shapes: Vec::new(),
+            colours: Vec::new(),
+        };
+        for i in 0..left.shapes.len().max(right.shapes.len()) {
+            let parent = if i >= right.shapes.len() || (i < left.shapes.len() && random()) {
+                left
+            } else {
+                right
+            };
+            layer.shapes.push(parent.shapes[i]);
+            layer.colours.push(parent.colours[i]);
+        }
+
+        for i in 0..layer.shapes.len() {
+            if random::<f32>() < mutation_rate {
+                layer.move_shape(i, 4096);
+                on_operator("move_shape");
+            }
+            if random::<f32>() < mutation_rate {
+                layer.reshape_shape(i, 2048);
+                on_operator("reshape_shape");
+            }
+            if random::<f32>() < mutation_rate {
+                layer.recolour_shape(i, space);
+                on_operator("recolour_shape");
+            }
+            if random::<f32>() < mutation_rate / 4.0 {
+                layer.replace_shape(i);
+                on_operator("replace_shape");
+            }
+            if random::<f32>() < mutation_rate / 4.0 {
+                let j = layer.get_random_shape();
+                layer.swap_shapes(i, j);
+                on_operator("swap_shapes");
+            }
+        }
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colour::Colour;
+    use crate::point::Point;
+
+    #[test]
+    fn moving_a_triangle_keeps_its_form() {
+        let points = [
+            Point::new(20000, 20000),
+            Point::new(30000, 25000),
+            Point::new(22000, 35000),
+        ];
+        let mut layer = ShapeLayer {
+            shapes: vec![Shape::Triangle { points }],
+            colours: vec![Colour::new_rgba(255, 0, 0, 128)],
+        };
+        for _ in 0..100 {
+            layer.move_shape(0, 4096);
+            let Shape::Triangle { points: moved } = layer.shapes[0] else {
+                panic!("The shape changed kind");
+            };
+            let offset = |p: Point, q: Point| (p.x as i32 - q.x as i32, p.y as i32 - q.y as i32);
+            for i in 1..3 {
+                assert_eq!(offset(moved[i], moved[0]), offset(points[i], points[0]));
+            }
+            // Keeps the triangle away from the edges, so that it is never saturated
+            layer.shapes[0] = Shape::Triangle { points };
+        }
+    }
+
+    #[test]
+    fn reshaping_stays_within_the_radius() {
+        let mut layer = ShapeLayer {
+            shapes: vec![Shape::Circle {
+                centre: Point::new(30000, 30000),
+                radius: 5000,
+            }],
+            colours: vec![Colour::new_rgba(255, 0, 0, 128)],
+        };
+        for _ in 0..100 {
+            layer.reshape_shape(0, 100);
+            let Shape::Circle { centre, radius } = layer.shapes[0] else {
+                panic!("The shape changed kind");
+            };
+            assert_eq!(centre, Point::new(30000, 30000));
+            assert!(radius.abs_diff(5000) <= 100);
+            layer.shapes[0] = Shape::Circle {
+                centre,
+                radius: 5000,
+            };
+        }
+    }
+
+    #[test]
+    fn swapping_shapes_keeps_their_colours() {
+        let mut layer = ShapeLayer::new_random(5);
+        let (shapes, colours) = (layer.shapes.clone(), layer.colours.clone());
+        layer.swap_shapes(1, 3);
+        assert_eq!((layer.shapes[1], layer.colours[1]), (shapes[3], colours[3]));
+        assert_eq!((layer.shapes[3], layer.colours[3]), (shapes[1], colours[1]));
+    }
+
+    #[test]
+    fn crossover_takes_each_shape_from_a_parent() {
+        let left = ShapeLayer::new_random(6);
+        let right = ShapeLayer::new_random(9);
+        for _ in 0..100 {
+            let child = ShapeLayer::breed_traced(&left, &right, 0.0, ColourSpace::Rgb, |_| {});
+            assert_eq!(child.shapes.len(), 9);
+            for (i, shape) in child.shapes.iter().enumerate() {
+                let from_left =
+                    i < 6 && (left.shapes[i], left.colours[i]) == (*shape, child.colours[i]);
+                let from_right = (right.shapes[i], right.colours[i]) == (*shape, child.colours[i]);
+                assert!(from_left || from_right);
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::point::Point;
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// The number of edges of the polygon that approximates a circle or ellipse
+const ELLIPSE_SEGMENTS: usize = 32;
+/// The largest extent of a new shape from its centre
+const SHAPE_SIZE: u16 = 8192;
+
+/// The geometry of a shape, positions are within `0..u16::MAX` like the points of an image.
+/// Radii are measured in units of the horizontal axis, so that circles are round in pixels
+/// whatever the image's aspect ratio
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Shape {
+    Triangle {
+        points: [Point; 3],
+    },
+    Circle {
+        centre: Point,
+        radius: u16,
+    },
+    /// An ellipse with the radii of its axes before it is rotated, `rotation` is a fraction of a
+    /// half turn clockwise (on screen)
+    Ellipse {
+        centre: Point,
+        radii: (u16, u16),
+        rotation: u16,
+    },
+}
+
+impl Shape {
+    /// Creates a triangle, circle or ellipse of a random size at a random position
+    pub fn new_random() -> Shape {
+        let mut thread_rng = rand::thread_rng();
+        let centre = Point::new(thread_rng.gen(), thread_rng.gen());
+        let mut radius = || thread_rng.gen_range(SHAPE_SIZE / 32..=SHAPE_SIZE);
+        match rand::thread_rng().gen_range(0..3) {
+            0 => Shape::Triangle {
+                points: [0; 3].map(|_| offset_point(centre, SHAPE_SIZE)),
+            },
+            1 => Shape::Circle {
+                centre,
+                radius: radius(),
+            },
+            _ => Shape::Ellipse {
+                centre,
+                radii: (radius(), radius()),
+                rotation: rand::random(),
+            },
+        }
+    }
+
+    /// Gets the triangles that cover the shape, circles and ellipses are approximated by a
+    /// polygon that is clipped to an image of `dimensions` pixels, and is covered by a fan of
+    /// triangles. The triangles don't overlap each other
+    pub(super) fn get_triangles<F>(&self, dimensions: (u32, u32), mut on_triangle: F)
+    where
+        F: FnMut([Point; 3]),
+    {
+        let (centre, radii, rotation) = match *self {
+            Shape::Triangle { points } => return on_triangle(points),
+            Shape::Circle { centre, radius } => (centre, (radius, radius), 0),
+            Shape::Ellipse {
+                centre,
+                radii,
+                rotation,
+            } => (centre, radii, rotation),
+        };
+        // Builds the polygon in pixels, so that the rotation and radii are in real proportions
+        let scale = (dimensions.0 as f64 / 65536.0, dimensions.1 as f64 / 65536.0);
+        let centre = (centre.x as f64 * scale.0, centre.y as f64 * scale.1);
+        let radii = (radii.0 as f64 * scale.0, radii.1 as f64 * scale.0);
+        let angle = rotation as f64 / 65536.0 * PI;
+        let polygon: Vec<(f64, f64)> = (0..ELLIPSE_SEGMENTS)
+            .map(|i| {
+                let t = i as f64 / ELLIPSE_SEGMENTS as f64 * 2.0 * PI;
+                let (x, y) = (radii.0 * t.cos(), radii.1 * t.sin());
+                (
+                    centre.0 + x * angle.cos() - y * angle.sin(),
+                    centre.1 + x * angle.sin() + y * angle.cos(),
+                )
+            })
+            .collect();
+        let polygon = clip_to_image(
+            polygon,
+            (u16::MAX as f64 * scale.0, u16::MAX as f64 * scale.1),
+        );
+
+        // Scales the polygon back to the image's points
+        let polygon: Vec<Point> = polygon
+            .into_iter()
+            .map(|(x, y)| {
+                Point::new(
+                    (x / scale.0).round().clamp(0.0, u16::MAX as f64) as u16,
+                    (y / scale.1).round().clamp(0.0, u16::MAX as f64) as u16,
+                )
+            })
+            .collect();
+        for i in 2..polygon.len() {
+            on_triangle([polygon[0], polygon[i - 1], polygon[i]]);
+        }
+    }
+}
+
+/// Gets a random point within `radius` of `point` in each axis
+pub(super) fn offset_point(point: Point, radius: u16) -> Point {
+    let mut thread_rng = rand::thread_rng();
+    let mut shift = |value: u16| {
+        thread_rng.gen_range(value.saturating_sub(radius)..=value.saturating_add(radius))
+    };
+    Point::new(shift(point.x), shift(point.y))
+}
+
+/// Clips the convex polygon to the rectangle from the origin to `extent`, by the
+/// Sutherland-Hodgman algorithm
+fn clip_to_image(mut polygon: Vec<(f64, f64)>, extent: (f64, f64)) -> Vec<(f64, f64)> {
+    for side in 0..4 {
+        // How far a point is outside of the side's boundary
+        let distance = |p: (f64, f64)| match side {
+            0 => -p.0,
+            1 => -p.1,
+            2 => p.0 - extent.0,
+            _ => p.1 - extent.1,
+        };
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for i in 0..polygon.len() {
+            let (from, to) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            let (from_distance, to_distance) = (distance(from), distance(to));
+            if from_distance <= 0.0 {
+                clipped.push(from);
+            }
+            // Adds the point where the edge crosses the boundary
+            if (from_distance <= 0.0) != (to_distance <= 0.0) {
+                let t = from_distance / (from_distance - to_distance);
+                clipped.push((from.0 + t * (to.0 - from.0), from.1 + t * (to.1 - from.1)));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Twice the signed area of the triangle, positive for the order used by the shapes
+    fn area([a, b, c]: [Point; 3]) -> i64 {
+        (b.x as i64 - a.x as i64) * (c.y as i64 - a.y as i64)
+            - (b.y as i64 - a.y as i64) * (c.x as i64 - a.x as i64)
+    }
+
+    #[test]
+    fn polygons_are_clipped_to_the_image() {
+        // A square straddling the bottom right corner keeps only the quarter inside the image
+        let square = vec![(90.0, 40.0), (110.0, 40.0), (110.0, 60.0), (90.0, 60.0)];
+        let clipped = clip_to_image(square, (100.0, 50.0));
+        assert_eq!(clipped.len(), 4);
+        for corner in [(90.0, 40.0), (100.0, 40.0), (100.0, 50.0), (90.0, 50.0)] {
+            assert!(clipped.contains(&corner));
+        }
+        // A polygon outside the image is clipped away
+        let outside = vec![(110.0, 10.0), (120.0, 10.0), (120.0, 20.0)];
+        assert!(clip_to_image(outside, (100.0, 50.0)).is_empty());
+    }
+
+    #[test]
+    fn a_circle_straddling_a_corner_is_clipped_to_a_quarter() {
+        let radius = 8192;
+        let circle = Shape::Circle {
+            centre: Point::new(u16::MAX, u16::MAX),
+            radius,
+        };
+        let mut total = 0;
+        circle.get_triangles((640, 640), |triangle| {
+            assert!(area(triangle) >= 0);
+            total += area(triangle);
+        });
+        // A quarter of the polygon approximating the circle, up to the rounding of its points
+        let polygon = ELLIPSE_SEGMENTS as f64
+            * (2.0 * PI / ELLIPSE_SEGMENTS as f64).sin()
+            * (radius as f64).powi(2);
+        assert!((total as f64 - polygon / 4.0).abs() < polygon * 0.01);
+    }
+
+    #[test]
+    fn ellipses_are_covered_by_a_fan_of_triangles() {
+        for rotation in [0, 10000, 32768] {
+            let ellipse = Shape::Ellipse {
+                centre: Point::new(30000, 30000),
+                radii: (8000, 4000),
+                rotation,
+            };
+            let mut triangles = Vec::new();
+            ellipse.get_triangles((640, 640), |triangle| triangles.push(triangle));
+            assert_eq!(triangles.len(), ELLIPSE_SEGMENTS - 2);
+            // The triangles are oriented the same way, so they don't overlap, and their area is
+            // that of the polygon approximating the ellipse
+            let total: i64 = triangles.iter().map(|&t| area(t)).sum();
+            assert!(triangles.iter().all(|&t| area(t) > 0));
+            let polygon = ELLIPSE_SEGMENTS as f64
+                * (2.0 * PI / ELLIPSE_SEGMENTS as f64).sin()
+                * 8000.0
+                * 4000.0;
+            assert!((total as f64 - polygon).abs() < polygon * 0.01);
+        }
+    }
+}
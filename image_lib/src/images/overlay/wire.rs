@@ -0,0 +1,117 @@
+use super::{Overlaid, Shape, ShapeLayer};
+use crate::colour::Colour;
+use genetic_algorithm_lib::workers::{Decode, Encode};
+
+/// A shape is encoded as a tag for its kind followed by its fields
+impl Encode for Shape {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Shape::Triangle { points } => {
+                0u8.encode(out);
+                points.encode(out);
+            }
+            Shape::Circle { centre, radius } => {
+                1u8.encode(out);
+                centre.encode(out);
+                radius.encode(out);
+            }
+            Shape::Ellipse {
+                centre,
+                radii,
+                rotation,
+            } => {
+                2u8.encode(out);
+                centre.encode(out);
+                radii.encode(out);
+                rotation.encode(out);
+            }
+        }
+    }
+}
+
+impl Decode for Shape {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some(match u8::decode(input)? {
+            0 => Shape::Triangle {
+                points: Decode::decode(input)?,
+            },
+            1 => Shape::Circle {
+                centre: Decode::decode(input)?,
+                radius: Decode::decode(input)?,
+            },
+            2 => Shape::Ellipse {
+                centre: Decode::decode(input)?,
+                radii: Decode::decode(input)?,
+                rotation: Decode::decode(input)?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// A layer is encoded as its shapes, then the colour of every shape
+impl Encode for ShapeLayer {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.shapes.encode(out);
+        self.colours.encode(out);
+    }
+}
+
+impl Decode for ShapeLayer {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let shapes = Vec::<Shape>::decode(input)?;
+        let colours = Vec::<Colour>::decode(input)?;
+        // Every shape needs a colour
+        if shapes.len() != colours.len() {
+            return None;
+        }
+        Some(ShapeLayer { shapes, colours })
+    }
+}
+
+/// An overlaid image is encoded as its base followed by its layer
+impl<I: Encode> Encode for Overlaid<I> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.base.encode(out);
+        self.layer.encode(out);
+    }
+}
+
+impl<I: Decode> Decode for Overlaid<I> {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let base = I::decode(input)?;
+        let layer = ShapeLayer::decode(input)?;
+        Some(Overlaid { base, layer })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::grid::GridImage;
+    use crate::point::Point;
+
+    #[test]
+    fn overlaid_images_round_trip() {
+        let image = Overlaid::new(
+            GridImage::<4, 4>::new_uniform_with_dimensions(640, 480),
+            ShapeLayer::new_random(30),
+        );
+        let mut genome = Vec::new();
+        image.encode(&mut genome);
+        let decoded = Overlaid::<GridImage<4, 4>>::decode(&mut genome.as_slice()).unwrap();
+        assert!(decoded == image);
+    }
+
+    #[test]
+    fn unknown_shapes_are_rejected() {
+        let mut genome = Vec::new();
+        Shape::Circle {
+            centre: Point::new(1, 2),
+            radius: 3,
+        }
+        .encode(&mut genome);
+        genome[0] = 3;
+        assert!(Shape::decode(&mut genome.as_slice()).is_none());
+    }
+}
@@ -0,0 +1,264 @@
+use crate::colour::Colour;
+use crate::images::grid::Tri;
+use crate::point::{FixedPoint, Point};
+use genetic_algorithm_lib::workers::Decode;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum AxisResolution {
+    Blocks1,
+    Blocks2,
+    Blocks4,
+    Blocks8,
+    Blocks16,
+    Blocks32,
+    Blocks64,
+    Blocks128,
+    Blocks256,
+    Blocks512,
+    Blocks1024,
+    Blocks2048,
+    Blocks4096,
+    Blocks8192,
+    Blocks16384,
+    Blocks32768,
+    Blocks65536,
+}
+
+impl AxisResolution {
+    pub fn get_pixel_size(self: &Self) -> u32 {
+        match self {
+            AxisResolution::Blocks1 => 65536,
+            AxisResolution::Blocks2 => 32768,
+            AxisResolution::Blocks4 => 16384,
+            AxisResolution::Blocks8 => 8192,
+            AxisResolution::Blocks16 => 4096,
+            AxisResolution::Blocks32 => 2048,
+            AxisResolution::Blocks64 => 1024,
+            AxisResolution::Blocks128 => 512,
+            AxisResolution::Blocks256 => 256,
+            AxisResolution::Blocks512 => 128,
+            AxisResolution::Blocks1024 => 64,
+            AxisResolution::Blocks2048 => 32,
+            AxisResolution::Blocks4096 => 16,
+            AxisResolution::Blocks8192 => 8,
+            AxisResolution::Blocks16384 => 4,
+            AxisResolution::Blocks32768 => 2,
+            AxisResolution::Blocks65536 => 1,
+        }
+    }
+    /// Finds the resolution with the given number of pixels, if there is one
+    pub fn from_pixel_count(count: u32) -> Option<AxisResolution> {
+        [
+            AxisResolution::Blocks1,
+            AxisResolution::Blocks2,
+            AxisResolution::Blocks4,
+            AxisResolution::Blocks8,
+            AxisResolution::Blocks16,
+            AxisResolution::Blocks32,
+            AxisResolution::Blocks64,
+            AxisResolution::Blocks128,
+            AxisResolution::Blocks256,
+            AxisResolution::Blocks512,
+            AxisResolution::Blocks1024,
+            AxisResolution::Blocks2048,
+            AxisResolution::Blocks4096,
+            AxisResolution::Blocks8192,
+            AxisResolution::Blocks16384,
+            AxisResolution::Blocks32768,
+            AxisResolution::Blocks65536,
+        ]
+        .into_iter()
+        .find(|resolution| resolution.get_pixel_count() == count)
+    }
+
+    pub fn get_pixel_count(self: &Self) -> u32 {
+        match self {
+            AxisResolution::Blocks1 => 1,
+            AxisResolution::Blocks2 => 2,
+            AxisResolution::Blocks4 => 4,
+            AxisResolution::Blocks8 => 8,
+            AxisResolution::Blocks16 => 16,
+            AxisResolution::Blocks32 => 32,
+            AxisResolution::Blocks64 => 64,
+            AxisResolution::Blocks128 => 128,
+            AxisResolution::Blocks256 => 256,
+            AxisResolution::Blocks512 => 512,
+            AxisResolution::Blocks1024 => 1024,
+            AxisResolution::Blocks2048 => 2048,
+            AxisResolution::Blocks4096 => 4096,
+            AxisResolution::Blocks8192 => 8192,
+            AxisResolution::Blocks16384 => 16384,
+            AxisResolution::Blocks32768 => 32768,
+            AxisResolution::Blocks65536 => 65536,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Resolution(pub AxisResolution, pub AxisResolution);
+
+impl Resolution {
+    /// Gets the size of the pixel blocks in each axis
+    pub fn get_pixel_size(self: Self) -> (u32, u32) {
+        (self.0.get_pixel_size(), self.1.get_pixel_size())
+    }
+
+    /// Gets the number of pixels in each axis
+    pub fn get_pixel_count(self: Self) -> (u32, u32) {
+        (self.0.get_pixel_count(), self.1.get_pixel_count())
+    }
+}
+
+/// How an image colours its triangles
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ColourMode {
+    /// Each triangle has a single colour
+    Flat,
+    /// Each vertex has a colour, and the colour is interpolated across each triangle from the
+    /// colours of its vertices
+    Gouraud,
+    /// Each triangle has a single colour, which is one of a palette of colours shared by the
+    /// whole image
+    Palette,
+}
+
+/// Decodes an image's real dimensions, an image without an area can't be rasterized so it is
+/// rejected
+pub(crate) fn decode_dimensions(input: &mut &[u8]) -> Option<(u32, u32)> {
+    let (width, height) = <(u32, u32)>::decode(input)?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// An image made of triangles, which can be rasterized. Internally all points are defined to be
+/// within `0..u16::MAX`, and are scaled to the image's real dimensions when rasterizing to pixels
+pub trait Triangulated {
+    /// Gets the real width and height of the image in pixels
+    fn get_dimensions(&self) -> (u32, u32);
+
+    /// Gets every triangle along with the colour at each of its vertices, with ColourMode::Flat
+    /// every vertex of a triangle has the triangle's colour
+    fn get_shaded_triangles<F>(&self, on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]);
+
+    /// Internally all points are defined to be within `0..u16::MAX`.
+    /// those ranges respectively.
+    /// With ColourMode::Gouraud, each triangle is given the average colour of its vertices
+    fn get_triangles<F>(&self, mut on_triangle: F)
+    where
+        F: FnMut(Point, Point, Point, Colour),
+    {
+        self.get_shaded_triangles(|[(first, a), (second, b), (third, c)]| {
+            let average = |a: u8, b: u8, c: u8| ((a as u32 + b as u32 + c as u32 + 1) / 3) as u8;
+            on_triangle(
+                first,
+                second,
+                third,
+                Colour::new_rgba(
+                    average(a.r, b.r, c.r),
+                    average(a.g, b.g, c.g),
+                    average(a.b, b.b, c.b),
+                    average(a.a, b.a, c.a),
+                ),
+            );
+        });
+    }
+
+    /// Internally all points are defined to be within `0..u16::MAX`.
+    ///
+    /// * `resolution` - The number of pixels that constitute a raster pixel in each axis, this
+    ///                  will then call on_point with raster pixels. e.g. With a resolution of
+    ///                  AxisResolution::Blocks64 (64 raster pixels), the i'th (0..64) raster pixel
+    ///                  is the (i*1024)'s pixel's colour in the image
+    fn rasterize_scanline<F>(&self, resolution: Resolution, offset: (u16, u16), on_point: F)
+    where
+        F: FnMut(Point, Colour),
+    {
+        self.rasterize_pixels(resolution.get_pixel_count(), offset, on_point);
+    }
+
+    /// Rasterizes the image to a grid of `size` pixels, which needn't be a power of two, e.g. the
+    /// image's real dimensions. The i'th pixel in an axis with n pixels is sampled at the domain
+    /// position `offset + i * 65536 / n`, on_point is called with the pixel's index. Triangles
+    /// are rasterized in the same way as `Tri::rasterize`, so a pixel on an edge shared by two
+    /// triangles is only drawn by one of them
+    fn rasterize_pixels<F>(&self, size: (u32, u32), offset: (u16, u16), mut on_point: F)
+    where
+        F: FnMut(Point, Colour),
+    {
+        assert!(
+            0 < size.0 && size.0 <= 65536 && 0 < size.1 && size.1 <= 65536,
+            "The raster must have between 1 and 65536 pixels in each axis"
+        );
+        self.get_shaded_triangles(|[(v1, c1), (v2, c2), (v3, c3)]| {
+            // Flat triangles don't need their colour interpolating
+            let shaded = c1 != c2 || c2 != c3;
+            let vertices =
+                [(v1, c1), (v2, c2), (v3, c3)].map(|(v, c)| (v.to_fixed(size, offset), c));
+            Tri::new(v1, v2, v3).rasterize_with_offset(size, offset, |p| {
+                let colour = if shaded {
+                    interpolate(vertices, FixedPoint::from_pixel(p.x as i64, p.y as i64))
+                } else {
+                    c1
+                };
+                on_point(p, colour);
+            });
+        });
+    }
+
+    /// Rasterizes the image in the same way as `rasterize_pixels`, but composites every triangle
+    /// over `background` with alpha blending, rather than reporting the triangles' own colours.
+    /// on_point is called once for every pixel, in row order, including any pixels which no
+    /// triangle covers
+    fn rasterize_composited<F>(
+        &self,
+        size: (u32, u32),
+        offset: (u16, u16),
+        background: Colour,
+        mut on_point: F,
+    ) where
+        F: FnMut(Point, Colour),
+    {
+        let mut pixels = vec![background; size.0 as usize * size.1 as usize];
+        self.rasterize_pixels(size, offset, |p, c| {
+            let pixel = &mut pixels[p.y as usize * size.0 as usize + p.x as usize];
+            *pixel = c.blend_over(*pixel);
+        });
+        for (i, colour) in pixels.into_iter().enumerate() {
+            on_point(
+                Point {
+                    x: (i % size.0 as usize) as u16,
+                    y: (i / size.0 as usize) as u16,
+                },
+                colour,
+            );
+        }
+    }
+}
+
+/// Interpolates the colours at the vertices of a triangle to the point `p`, by the barycentric
+/// coordinates of `p`
+fn interpolate(vertices: [(FixedPoint, Colour); 3], p: FixedPoint) -> Colour {
+    let [(v1, c1), (v2, c2), (v3, c3)] = vertices;
+    let weights = [
+        FixedPoint::orientation(v2, v3, p) as f64,
+        FixedPoint::orientation(v3, v1, p) as f64,
+        FixedPoint::orientation(v1, v2, p) as f64,
+    ];
+    let total: f64 = weights.iter().sum();
+    let channel = |channel: fn(&Colour) -> u8| {
+        let value = [c1, c2, c3]
+            .iter()
+            .zip(weights)
+            .map(|(c, weight)| channel(c) as f64 * weight)
+            .sum::<f64>()
+            / total;
+        value.round().clamp(0.0, 255.0) as u8
+    };
+    Colour::new_rgba(
+        channel(|c| c.r),
+        channel(|c| c.g),
+        channel(|c| c.b),
+        channel(|c| c.a),
+    )
+}
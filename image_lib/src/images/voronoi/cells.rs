@@ -0,0 +1,123 @@
+use crate::point::Point;
+
+/// Gets the Voronoi cell of every site, see `VoronoiImage::get_cells`. Each cell starts as the
+/// whole image and is clipped by the half plane closer to its site than to each other site.
+/// Distances are measured in pixels, so the sites are scaled by the image's dimensions first
+pub(super) fn get_cells(sites: &[Point], dimensions: (u32, u32)) -> Vec<Vec<Point>> {
+    let scale = (dimensions.0 as f64 / 65536.0, dimensions.1 as f64 / 65536.0);
+    let scaled: Vec<(f64, f64)> = sites
+        .iter()
+        .map(|site| (site.x as f64 * scale.0, site.y as f64 * scale.1))
+        .collect();
+    let (right, bottom) = (u16::MAX as f64 * scale.0, u16::MAX as f64 * scale.1);
+
+    scaled
+        .iter()
+        .enumerate()
+        .map(|(i, site)| {
+            let mut cell = vec![(0.0, 0.0), (right, 0.0), (right, bottom), (0.0, bottom)];
+            for (j, other) in scaled.iter().enumerate() {
+                if i != j && !cell.is_empty() {
+                    cell = clip(&cell, *site, *other);
+                }
+            }
+            // Scales the cell back to the image's points
+            cell.into_iter()
+                .map(|(x, y)| {
+                    Point::new(
+                        (x / scale.0).round().clamp(0.0, u16::MAX as f64) as u16,
+                        (y / scale.1).round().clamp(0.0, u16::MAX as f64) as u16,
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Clips the convex polygon to the half plane of points at least as close to `site` as to
+/// `other`, by the Sutherland-Hodgman algorithm
+fn clip(polygon: &[(f64, f64)], site: (f64, f64), other: (f64, f64)) -> Vec<(f64, f64)> {
+    // A point p is in the half plane when (other - site) . p <= (|other|^2 - |site|^2) / 2, the
+    // distance is how far the point is outside of it
+    let normal = (other.0 - site.0, other.1 - site.1);
+    let bound = (other.0.powi(2) + other.1.powi(2) - site.0.powi(2) - site.1.powi(2)) / 2.0;
+    let distance = |p: (f64, f64)| normal.0 * p.0 + normal.1 * p.1 - bound;
+
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for i in 0..polygon.len() {
+        let (from, to) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        let (from_distance, to_distance) = (distance(from), distance(to));
+        if from_distance <= 0.0 {
+            clipped.push(from);
+        }
+        // Adds the point where the edge crosses the boundary of the half plane
+        if (from_distance <= 0.0) != (to_distance <= 0.0) {
+            let t = from_distance / (from_distance - to_distance);
+            clipped.push((from.0 + t * (to.0 - from.0), from.1 + t * (to.1 - from.1)));
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Twice the signed area of the polygon, positive for the order used by the cells
+    fn area(polygon: &[Point]) -> f64 {
+        (0..polygon.len())
+            .map(|i| {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                a.x as f64 * b.y as f64 - b.x as f64 * a.y as f64
+            })
+            .sum()
+    }
+
+    #[test]
+    fn cells_tile_the_image() {
+        let mut thread_rng = rand::thread_rng();
+        for dimensions in [(640, 480), (100, 1000)] {
+            let sites: Vec<Point> = (0..30)
+                .map(|_| Point::new(thread_rng.gen(), thread_rng.gen()))
+                .collect();
+            let cells = get_cells(&sites, dimensions);
+            assert_eq!(cells.len(), sites.len());
+
+            // The cells don't overlap and cover the image, up to the rounding of their vertices
+            let total: f64 = cells.iter().map(|cell| area(cell)).sum();
+            let image = 2.0 * u16::MAX as f64 * u16::MAX as f64;
+            assert!((total - image).abs() < image * 1e-3);
+            for cell in &cells {
+                assert!(area(cell) >= 0.0);
+            }
+
+            // A random point lies in the cell of its nearest site, measured in pixels
+            let scale = (dimensions.0 as f64, dimensions.1 as f64);
+            for _ in 0..1000 {
+                let p: (f64, f64) = (
+                    thread_rng.gen_range(0.0..65535.0),
+                    thread_rng.gen_range(0.0..65535.0),
+                );
+                let distance = |site: &Point| {
+                    ((site.x as f64 - p.0) * scale.0).powi(2)
+                        + ((site.y as f64 - p.1) * scale.1).powi(2)
+                };
+                let nearest = (0..sites.len())
+                    .min_by(|&i, &j| distance(&sites[i]).total_cmp(&distance(&sites[j])))
+                    .unwrap();
+                let cell = &cells[nearest];
+                for i in 0..cell.len() {
+                    let (a, b) = (cell[i], cell[(i + 1) % cell.len()]);
+                    let (edge, to_p) = (
+                        (b.x as f64 - a.x as f64, b.y as f64 - a.y as f64),
+                        (p.0 - a.x as f64, p.1 - a.y as f64),
+                    );
+                    // The distance of p inside the edge, allowing for the rounding of the cell
+                    let inside = (edge.0 * to_p.1 - edge.1 * to_p.0) / edge.0.hypot(edge.1);
+                    assert!(inside > -2.0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::images::voronoi::VoronoiImage;
+use crate::images::{BreedMetadata, Breedable, ImageMember};
+
+/// A member which evolves a Voronoi image
+pub type VoronoiMember<'a, U> = ImageMember<'a, U, VoronoiImage>;
+
+impl Breedable for VoronoiImage {
+    fn breed_traced<F>(left: &Self, right: &Self, metadata: &BreedMetadata, on_operator: F) -> Self
+    where
+        F: FnMut(&'static str),
+    {
+        VoronoiImage::breed_traced(
+            left,
+            right,
+            metadata.get_mutation_rate(),
+            metadata.get_mutation_space(),
+            on_operator,
+        )
+    }
+}
@@ -0,0 +1,191 @@
+/// An image whose structure is a free set of coloured sites, every pixel takes the colour of its
+/// nearest site, so the image is partitioned into the Voronoi cells of the sites. The root of the
+/// image is top left
+use crate::colour::Colour;
+use crate::images::Triangulated;
+use crate::point::Point;
+use rand::Rng;
+mod cells;
+mod member;
+mod mutation;
+mod wire;
+pub use member::VoronoiMember;
+
+#[derive(Clone, PartialEq)]
+pub struct VoronoiImage {
+    /// The position of every site, there is always at least one site and no two sites have the
+    /// same position
+    sites: Vec<Point>,
+    /// The colour of every site
+    colours: Vec<Colour>,
+    /// The real width and height of the image in pixels, sites are always within `0..u16::MAX`
+    /// and are scaled to these dimensions when rasterizing to pixels, distances between sites
+    /// and pixels are measured after scaling
+    dimensions: (u32, u32),
+}
+
+impl VoronoiImage {
+    /// Creates an image which is `width` x `height` pixels, with `count` sites placed at random,
+    /// every site has a random colour
+    pub fn new_random(count: usize, width: u32, height: u32) -> VoronoiImage {
+        assert!(width > 0 && height > 0, "The image must have an area");
+        assert!(count > 0, "The image must have a site");
+        let mut image = VoronoiImage {
+            sites: Vec::with_capacity(count),
+            colours: Vec::with_capacity(count),
+            dimensions: (width, height),
+        };
+        let mut thread_rng = rand::thread_rng();
+        while image.sites.len() < count {
+            let site = Point::new(thread_rng.gen(), thread_rng.gen());
+            image.insert_site(site, random_colour());
+        }
+        image
+    }
+
+    pub fn get_sites(&self) -> &[Point] {
+        &self.sites
+    }
+
+    pub fn get_colours(&self) -> &[Colour] {
+        &self.colours
+    }
+
+    /// Gets the Voronoi cell of every site, clipped to the image, each cell is a convex polygon
+    /// with its vertices in (clockwise on screen) order, a cell may be empty if it lies outside
+    /// the image
+    pub fn get_cells(&self) -> Vec<Vec<Point>> {
+        cells::get_cells(&self.sites, self.dimensions)
+    }
+
+    /// Gets the index of the site nearest to the position (x, y), ties are given to the site with
+    /// the lowest index
+    pub fn get_nearest_site(&self, x: f64, y: f64) -> usize {
+        let scale = self.get_scale();
+        let distance = |site: &Point| {
+            ((site.x as f64 - x) * scale.0).powi(2) + ((site.y as f64 - y) * scale.1).powi(2)
+        };
+        let mut nearest = 0;
+        let mut nearest_distance = f64::INFINITY;
+        for (i, site) in self.sites.iter().enumerate() {
+            let distance = distance(site);
+            if distance < nearest_distance {
+                nearest = i;
+                nearest_distance = distance;
+            }
+        }
+        nearest
+    }
+
+    /// Gets the size of one unit of each axis in pixels
+    fn get_scale(&self) -> (f64, f64) {
+        (
+            self.dimensions.0 as f64 / 65536.0,
+            self.dimensions.1 as f64 / 65536.0,
+        )
+    }
+}
+
+/// The triangles of the image are a fan of each site's cell, which are only used to export the
+/// image, as the vertices of the cells are rounded to the nearest point. Rasterizing finds the
+/// nearest site of every pixel, so that the cells are exact
+impl Triangulated for VoronoiImage {
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn get_shaded_triangles<F>(&self, mut on_triangle: F)
+    where
+        F: FnMut([(Point, Colour); 3]),
+    {
+        for (cell, colour) in self.get_cells().iter().zip(&self.colours) {
+            for i in 2..cell.len() {
+                on_triangle([
+                    (cell[0], *colour),
+                    (cell[i - 1], *colour),
+                    (cell[i], *colour),
+                ]);
+            }
+        }
+    }
+
+    /// Every pixel takes the colour of its nearest site, which is found by comparing every site,
+    /// so rasterizing takes time proportional to the number of pixels times the number of sites
+    fn rasterize_pixels<F>(&self, size: (u32, u32), offset: (u16, u16), mut on_point: F)
+    where
+        F: FnMut(Point, Colour),
+    {
+        assert!(
+            0 < size.0 && size.0 <= 65536 && 0 < size.1 && size.1 <= 65536,
+            "The raster must have between 1 and 65536 pixels in each axis"
+        );
+        // The i'th pixel in an axis with n pixels is sampled at the domain position
+        // offset + i * 65536 / n, pixels sampled outside the domain aren't covered by the image
+        let sample =
+            |i: u32, offset: u16, count: u32| offset as f64 + i as f64 * 65536.0 / count as f64;
+        for row in 0..size.1 {
+            let y = sample(row, offset.1, size.1);
+            if y > u16::MAX as f64 {
+                break;
+            }
+            for column in 0..size.0 {
+                let x = sample(column, offset.0, size.0);
+                if x > u16::MAX as f64 {
+                    break;
+                }
+                on_point(
+                    Point::new(column as u16, row as u16),
+                    self.colours[self.get_nearest_site(x, y)],
+                );
+            }
+        }
+    }
+}
+
+fn random_colour() -> Colour {
+    Colour::new(rand::random(), rand::random(), rand::random())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_are_measured_in_pixels() {
+        let mut image = VoronoiImage::new_random(1, 1000, 10);
+        image.sites = vec![Point::new(0, 30000), Point::new(20000, 0)];
+        image.colours = vec![Colour::new(0, 0, 0); 2];
+        // The second site is much further away in the domain, but the image is so short that it
+        // is closer in pixels
+        assert_eq!(image.get_nearest_site(12000.0, 30000.0), 1);
+        // Ties are given to the first site
+        image.sites = vec![Point::new(20000, 0), Point::new(0, 0)];
+        assert_eq!(image.get_nearest_site(10000.0, 0.0), 0);
+    }
+
+    #[test]
+    fn every_pixel_takes_its_nearest_sites_colour() {
+        let mut image = VoronoiImage::new_random(40, 640, 480);
+        image.colours = (0..40).map(|i| Colour::new(i * 6, 0, 0)).collect();
+        let size = (97, 61);
+        let mut covered = vec![0u8; size.0 as usize * size.1 as usize];
+        image.rasterize_pixels(size, (0, 0), |p, c| {
+            covered[p.y as usize * size.0 as usize + p.x as usize] += 1;
+            // The pixel's site is as close as any other, measured in pixels
+            let (x, y) = (
+                p.x as f64 * 65536.0 / size.0 as f64 * 640.0 / 65536.0,
+                p.y as f64 * 65536.0 / size.1 as f64 * 480.0 / 65536.0,
+            );
+            let distance = |site: &Point| {
+                (site.x as f64 * 640.0 / 65536.0 - x).powi(2)
+                    + (site.y as f64 * 480.0 / 65536.0 - y).powi(2)
+            };
+            let site = &image.sites[c.r as usize / 6];
+            assert!(image
+                .sites
+                .iter()
+                .all(|s| distance(site) <= distance(s) + 1e-9));
+        });
+        assert!(covered.iter().all(|&n| n == 1));
+    }
+}
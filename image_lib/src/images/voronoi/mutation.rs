@@ -0,0 +1,131 @@
+use super::VoronoiImage;
+use crate::colour::{Colour, ColourSpace};
+use crate::point::Point;
+use rand::{random, Rng};
+
+impl VoronoiImage {
+    /// Inserts a site, returns false if there is already a site at the position
+    pub fn insert_site(&mut self, site: Point, colour: Colour) -> bool {
+        if self.sites.contains(&site) {
+            return false;
+        }
+        self.sites.push(site);
+        self.colours.push(colour);
+        true
+    }
+
+    /// Deletes the site at `index`, the last site can't be deleted
+    pub fn delete_site(&mut self, index: usize) {
+        assert!(self.sites.len() > 1, "The last site can't be deleted");
+        self.sites.swap_remove(index);
+        self.colours.swap_remove(index);
+    }
+
+    /// Moves the site at `index` by up to `radius` in each axis, the site isn't moved if there is
+    /// already a site at the new position
+    pub fn move_site(&mut self, index: usize, radius: u16) {
+        let mut thread_rng = rand::thread_rng();
+        let mut shift = |value: u16| {
+            thread_rng.gen_range(value.saturating_sub(radius)..=value.saturating_add(radius))
+        };
+        let site = self.sites[index];
+        let moved = Point::new(shift(site.x), shift(site.y));
+        if !self.sites.contains(&moved) {
+            self.sites[index] = moved;
+        }
+    }
+
+    /// Perturbs the colour of the site at `index` in `space`, see `Colour::perturb`
+    pub fn recolour_site(&mut self, index: usize, space: ColourSpace) {
+        self.colours[index] = self.colours[index].perturb(space);
+    }
+
+    /// Gets the index of a random site
+    pub fn get_random_site(&self) -> usize {
+        rand::thread_rng().gen_range(0..self.sites.len())
+    }
+}
+
+/// A collection of functions to aid genetic mutation and breeding
+impl VoronoiImage {
+    pub fn breed(
+        left: &VoronoiImage,
+        right: &VoronoiImage,
+        mutation_rate: f32,
+        space: ColourSpace,
+    ) -> VoronoiImage {
+        VoronoiImage::breed_traced(left, right, mutation_rate, space, |_| {})
+    }
+
+    /// Breeds the images with a spatial crossover, the child has the sites of the left parent on
+    /// one side of a random line and the sites of the right parent on the other.
+    /// Each site of the child then has a `mutation_rate` chance of being moved and of its colour
+    /// being mutated in `space`, and a quarter of that chance of being deleted, a site is
+    /// inserted with the same chance, so that the number of sites is stable.
+    /// `on_operator` is called with the name of each operator that is applied to the child
+    pub fn breed_traced<F>(
+        left: &VoronoiImage,
+        right: &VoronoiImage,
+        mutation_rate: f32,
+        space: ColourSpace,
+        mut on_operator: F,
+    ) -> VoronoiImage
+    where
+        F: FnMut(&'static str),
+    {
+        let mut thread_rng = rand::thread_rng();
+
+        // Cuts the images along a random vertical or horizontal line
+        on_operator("spatial_crossover");
+        let vertical: bool = random();
+        let cut: u16 = thread_rng.gen();
+        let side = |p: &Point| if vertical { p.x < cut } else { p.y < cut };
+        let mut image = VoronoiImage {
+            sites: Vec::new(),
+            colours: Vec::new(),
+            ..left.clone()
+        };
+        for (parent, from_left) in [(left, true), (right, false)] {
+            for (site, colour) in parent.sites.iter().zip(&parent.colours) {
+                if side(site) == from_left {
+                    image.insert_site(*site, *colour);
+                }
+            }
+        }
+        // The image must have a site, so if the cut leaves none the left parent's first is kept
+        if image.sites.is_empty() {
+            image.insert_site(left.sites[0], left.colours[0]);
+        }
+
+        // Mutates the sites, the sites are visited in reverse so that deleting a site doesn't
+        // skip the site that replaces it
+        let mut insertions = 0;
+        for i in (0..image.sites.len()).rev() {
+            if random::<f32>() < mutation_rate {
+                image.move_site(i, 4096);
+                on_operator("move_site");
+            }
+            if random::<f32>() < mutation_rate {
+                image.recolour_site(i, space);
+                on_operator("recolour_site");
+            }
+            if random::<f32>() < mutation_rate / 4.0 && image.sites.len() > 1 {
+                image.delete_site(i);
+                on_operator("delete_site");
+            }
+            if random::<f32>() < mutation_rate / 4.0 {
+                insertions += 1;
+            }
+        }
+        // An inserted site takes the colour of the site nearest to it, so it doesn't change the
+        // image until it is moved or recoloured
+        for _ in 0..insertions {
+            let site = Point::new(thread_rng.gen(), thread_rng.gen());
+            let colour = image.colours[image.get_nearest_site(site.x as f64, site.y as f64)];
+            if image.insert_site(site, colour) {
+                on_operator("insert_site");
+            }
+        }
+        image
+    }
+}
@@ -0,0 +1,60 @@
+use super::VoronoiImage;
+use crate::colour::Colour;
+use crate::images::decode_dimensions;
+use crate::point::Point;
+use genetic_algorithm_lib::workers::{Decode, Encode};
+
+/// A Voronoi image is encoded as its sites, then the colour of every site and finally its real
+/// dimensions
+impl Encode for VoronoiImage {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.sites.encode(out);
+        self.colours.encode(out);
+        self.dimensions.encode(out);
+    }
+}
+
+impl Decode for VoronoiImage {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let sites = Vec::<Point>::decode(input)?;
+        let colours = Vec::<Colour>::decode(input)?;
+        // Every site needs a colour, and there must be a site
+        if sites.len() != colours.len() || sites.is_empty() {
+            return None;
+        }
+        let dimensions = decode_dimensions(input)?;
+        Some(VoronoiImage {
+            sites,
+            colours,
+            dimensions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_round_trip() {
+        let image = VoronoiImage::new_random(50, 640, 480);
+        let mut genome = Vec::new();
+        image.encode(&mut genome);
+        assert!(VoronoiImage::decode(&mut genome.as_slice()).unwrap() == image);
+    }
+
+    #[test]
+    fn images_without_a_site_or_colour_are_rejected() {
+        let mut image = VoronoiImage::new_random(10, 640, 480);
+        image.colours.pop();
+        let mut genome = Vec::new();
+        image.encode(&mut genome);
+        assert!(VoronoiImage::decode(&mut genome.as_slice()).is_none());
+
+        image.sites.clear();
+        image.colours.clear();
+        let mut genome = Vec::new();
+        image.encode(&mut genome);
+        assert!(VoronoiImage::decode(&mut genome.as_slice()).is_none());
+    }
+}
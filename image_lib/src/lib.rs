@@ -19,6 +19,7 @@ pub mod point;
 #[cfg(test)]
 mod tests {
     use crate::images::grid::GridImage;
+    use crate::images::{AxisResolution, Resolution, Triangulated};
     use std::time::Instant;
 
     #[test]
@@ -33,7 +34,8 @@ mod tests {
         let start = Instant::now();
         for i in 0..n {
             // image.mutate_structure();
-            image.rasterize_scanline((8, 8), (0, 0), |point, colour| {
+            let resolution = Resolution(AxisResolution::Blocks256, AxisResolution::Blocks256);
+            image.rasterize_scanline(resolution, (0, 0), |point, colour| {
                 foo += (point.x + point.y) as i64;
                 points_rendered += 1;
             });
@@ -1,3 +1,5 @@
+use genetic_algorithm_lib::workers::{Decode, Encode};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Point {
     pub x: u16,
@@ -9,19 +11,162 @@ impl Point {
         Point { x, y }
     }
 
-    // Scales up the x and y coordinates
+    // Scales up the x and y coordinates, coordinates which would be beyond the domain saturate
+    // at u16::MAX
     pub fn scale_up(&self, scale: (u16, u16)) -> Point {
         Point {
-            x: self.x * scale.0,
-            y: self.y * scale.1,
+            x: self.x.saturating_mul(scale.0),
+            y: self.y.saturating_mul(scale.1),
         }
     }
 
-    // Scales down the x and y coordinates, e.g. a scale of 2 would half the values
+    // Scales down the x and y coordinates, e.g. a scale of 2 would half the values, this
+    // truncates so the point snaps to the scaled grid, see `to_fixed` for an exact position
     pub fn scale_down(&self, scale: (u16, u16)) -> Point {
         Point {
             x: self.x / scale.0,
             y: self.y / scale.1,
         }
     }
+
+    /// Gets the exact position of the point in a raster of `count` pixels in each axis, whose
+    /// first pixel is sampled at `offset` in the domain
+    pub fn to_fixed(&self, count: (u32, u32), offset: (u16, u16)) -> FixedPoint {
+        FixedPoint {
+            x: (self.x as i64 - offset.0 as i64) * count.0 as i64,
+            y: (self.y as i64 - offset.1 as i64) * count.1 as i64,
+        }
+    }
+}
+
+/// The number of units in a raster pixel of a FixedPoint. A domain position (within
+/// `0..65536`) multiplied by the number of pixels in its axis is exactly its position in raster
+/// pixels with 16 fractional bits, so no precision is lost at any resolution
+pub const FIXED_POINT_ONE: i64 = 65536;
+
+/// A position in raster pixels with `FIXED_POINT_ONE` units per pixel, so that vertices keep
+/// their sub-pixel positions rather than snapping to the raster's pixels. The i'th pixel of an
+/// axis is sampled at exactly `i * FIXED_POINT_ONE`
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FixedPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl FixedPoint {
+    pub fn new(x: i64, y: i64) -> FixedPoint {
+        FixedPoint { x, y }
+    }
+
+    /// Gets the position at which the pixel is sampled
+    pub fn from_pixel(column: i64, row: i64) -> FixedPoint {
+        FixedPoint {
+            x: column * FIXED_POINT_ONE,
+            y: row * FIXED_POINT_ONE,
+        }
+    }
+
+    /// Twice the signed area of the triangle, positive when its vertices are in clockwise order
+    /// on screen (with y pointing down). Positions can be up to 2^32 units in magnitude, so the
+    /// products are taken in i128 and never overflow
+    pub fn orientation(first: FixedPoint, second: FixedPoint, third: FixedPoint) -> i128 {
+        (second.x as i128 - first.x as i128) * (third.y as i128 - first.y as i128)
+            - (second.y as i128 - first.y as i128) * (third.x as i128 - first.x as i128)
+    }
+}
+
+/// Gets the first pixel whose sample is at or after the fixed point position
+pub(crate) fn first_pixel_from(position: i64) -> i64 {
+    position.div_euclid(FIXED_POINT_ONE) + (position.rem_euclid(FIXED_POINT_ONE) != 0) as i64
+}
+
+impl Encode for Point {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.x, self.y).encode(out);
+    }
+}
+
+impl Decode for Point {
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let (x, y) = Decode::decode(input)?;
+        Some(Point { x, y })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_on_samples_convert_to_whole_pixels() {
+        // A 64 pixel raster has a sample every 1024 units of the domain
+        let point = Point::new(1024, 2048);
+        assert_eq!(
+            point.to_fixed((64, 64), (0, 0)),
+            FixedPoint::from_pixel(1, 2)
+        );
+        assert_eq!(
+            point.to_fixed((64, 64), (1024, 0)),
+            FixedPoint::from_pixel(0, 2)
+        );
+        let fixed = point.to_fixed((64, 32), (0, 0));
+        assert_eq!(
+            (first_pixel_from(fixed.x), first_pixel_from(fixed.y)),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn sub_pixel_positions_are_kept() {
+        // With 97 pixels a sample lies every 65536 / 97 units, so few points lie on a sample
+        let fixed = Point::new(1000, 1).to_fixed((97, 97), (0, 0));
+        assert_eq!(fixed, FixedPoint::new(97000, 97));
+        assert_eq!(
+            (first_pixel_from(fixed.x), first_pixel_from(fixed.y)),
+            (2, 1)
+        );
+
+        // Positions round up to the first pixel at or after them, including negative positions
+        // before the offset
+        for (position, pixel) in [
+            (-65537, -1),
+            (-65536, -1),
+            (-1, 0),
+            (0, 0),
+            (1, 1),
+            (65536, 1),
+            (65537, 2),
+        ] {
+            assert_eq!(first_pixel_from(position), pixel);
+        }
+        let before = Point::new(0, 0).to_fixed((64, 64), (512, 512));
+        assert_eq!(
+            (first_pixel_from(before.x), first_pixel_from(before.y)),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn the_extremes_of_the_domain_dont_overflow() {
+        let max = Point::new(u16::MAX, u16::MAX);
+        let fixed = max.to_fixed((65536, 65536), (0, 0));
+        assert_eq!(fixed, FixedPoint::from_pixel(65535, 65535));
+        let origin = Point::new(0, 0).to_fixed((65536, 65536), (u16::MAX, u16::MAX));
+        assert_eq!(origin, FixedPoint::from_pixel(-65535, -65535));
+
+        // The far edges of the domain lie after the last sample of rasters smaller than the domain
+        let fixed = max.to_fixed((97, 97), (0, 0));
+        assert_eq!(first_pixel_from(fixed.x), 97);
+
+        let corner = |x: u16, y: u16| Point::new(x, y).to_fixed((65536, 65536), (0, 0));
+        let area = FixedPoint::orientation(corner(0, 0), corner(u16::MAX, 0), corner(0, u16::MAX));
+        assert_eq!(area, (u16::MAX as i128 * 65536).pow(2));
+    }
+
+    #[test]
+    fn scaling_up_saturates() {
+        let point = Point::new(40000, 2);
+        assert_eq!(point.scale_up((2, 3)), Point::new(u16::MAX, 6));
+        assert_eq!(point.scale_up((1, 1)), point);
+    }
 }